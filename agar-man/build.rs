@@ -0,0 +1,69 @@
+// Precompute the index of the built-in dictionary, so the solver does not have to parse and
+// group 178k words every time it starts.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+#[path = "src/layout.rs"]
+#[allow(dead_code)]
+mod layout;
+
+use layout::Layout;
+
+const DICTIONARY: &str = "src/dictionary_counts.txt";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", DICTIONARY);
+    println!("cargo:rerun-if-changed=src/layout.rs");
+
+    let text = fs::read_to_string(DICTIONARY).expect("built-in dictionary is readable");
+
+    // the same parsing and ranking as `WordList::from_tsv` and `Index::new`
+    let entries = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            layout::parse_entry(line)
+                .unwrap_or_else(|| panic!("{}:{}: invalid count", DICTIONARY, line_number + 1))
+        });
+    let (word_counts, skipped) = layout::rank_words(entries);
+    if let Some(word) = skipped.first() {
+        println!(
+            "cargo:warning={}: skipped {} words with characters other than a-z, such as {:?}",
+            DICTIONARY,
+            skipped.len(),
+            word
+        );
+    }
+
+    let index_map = layout::rank_letters(word_counts.iter().map(|(word, _)| word.as_str()));
+    let counters = word_counts
+        .iter()
+        .map(|(word, _)| {
+            layout::counter_of(word, &index_map).unwrap_or_else(|letter| {
+                panic!("{}: {:?} has too many {:?}s", DICTIONARY, word, letter)
+            })
+        })
+        .collect::<Vec<_>>();
+    let (words, counts) = word_counts.into_iter().unzip();
+    let index = Layout::new(words, counts, index_map, &counters).encode();
+
+    // the words compress about as well as the word list; their ids, which put them back in
+    // ranked order, hardly compress at all, but spare the solver sorting them when it starts
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&index).expect("compression succeeds");
+    let compressed = encoder.finish().expect("compression succeeds");
+
+    let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    fs::write(Path::new(&out_dir).join("dictionary.idx.gz"), compressed)
+        .expect("index is writable");
+
+    // for `builtin_dictionary_size`
+    println!("cargo:rustc-env=AGAR_MAN_INDEX_SIZE={}", index.len());
+    println!("cargo:rustc-env=AGAR_MAN_WORD_LIST_SIZE={}", text.len());
+}
@@ -1,48 +1,268 @@
 /* tslint:disable */
 /* eslint-disable */
+
 /**
-* @param {string} seed
-* @param {number} min_length
-* @param {number} max_num_words
-* @param {string} excludes
-* @param {string} includes
-* @param {number} top_n
-* @returns {ResultsStruct}
-*/
-export function js_generate(seed: string, min_length: number, max_num_words: number, excludes: string, includes: string, top_n: number): ResultsStruct;
+ * A single anagram, with the frequency information used to rank it.
+ */
+export class Anagram {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    letters_used: number;
+    per_word_counts: Uint32Array;
+    score: number;
+    words: string[];
+}
+
 /**
-*/
-export class ResultsStruct {
-  free(): void;
+ * JS handle to an `AnagramSolver`; construct it once and call `generate` for every `Query`.
+ */
+export class AnagramSolver {
+    free(): void;
+    [Symbol.dispose](): void;
+    /**
+     * Add a name, brand or other word missing from the dictionary; see `add_word`.
+     */
+    add_word(word: string, count?: number | null): boolean;
+    /**
+     * Solver over an index saved with `to_index`.
+     */
+    static from_index(bytes: Uint8Array): AnagramSolver;
+    /**
+     * Solver over a `Map` from words to frequency counts.
+     */
+    static from_map(map: Map<any, any>): AnagramSolver;
+    /**
+     * Solver over `word\tcount` lines, e.g. the text of a fetched file.
+     */
+    static from_tsv(text: string): AnagramSolver;
+    /**
+     * Solver over a list of words, ranked in the order given.
+     */
+    static from_words(words: string[]): AnagramSolver;
+    generate(query: Query): ResultsStruct;
+    /**
+     * Only the `k` best anagrams of a query, without enumerating the rest; see `solve_best`.
+     */
+    generate_best(query: Query, k: number): ResultsStruct;
+    constructor();
+    /**
+     * The anagrams from `offset` to `offset + limit` by score; see `AnagramSolver::solve_page`.
+     */
+    page(query: Query, offset: number, limit: number): ResultsStruct;
+    remove_word(word: string): boolean;
+    /**
+     * Carry on from a stream's `resume_token`, e.g. to load more without keeping the stream.
+     */
+    resume(query: Query, token: string): AnagramStream;
+    /**
+     * Draw random anagrams, favouring common words if `weighted`; seeds must be below 2^53.
+     */
+    sample(query: Query, num_samples: number, weighted: boolean, seed: number): ResultsStruct;
+    /**
+     * Start a lazy search; pull results with `next_batch` instead of waiting for all of them.
+     */
+    stream(query: Query): AnagramStream;
+    to_index(): Uint8Array;
+}
+
 /**
-*/
-  anagrams: Array<any>;
+ * JS handle to an in-progress search, returned by `AnagramSolver.stream`.
+ */
+export class AnagramStream {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    /**
+     * Stop the search; later batches come back empty.
+     */
+    cancel(): void;
+    /**
+     * Up to `n` more anagrams, in search order; an empty array means the search is done.
+     */
+    next_batch(n: number): Anagram[];
+    /**
+     * Every word that fits in the seed.
+     */
+    partials(): string[];
+    /**
+     * A token for `AnagramSolver.resume`, or undefined once the search is done.
+     */
+    resume_token(): string | undefined;
+    /**
+     * Why the search stopped early, if it did.
+     */
+    truncated(): string | undefined;
+}
+
 /**
-*/
-  partials: Array<any>;
+ * JS handle to a `Bigrams` table, shared by every `Query` it is set on.
+ */
+export class Bigrams {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    /**
+     * A table from `first\tsecond\tcount` lines, e.g. the text of a fetched file.
+     */
+    static from_tsv(text: string): Bigrams;
 }
 
+/**
+ * How many bytes the built-in dictionary adds to the binary or wasm bundle.
+ */
+export class DictionarySize {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    compressed: number;
+    uncompressed: number;
+    word_list: number;
+}
+
+/**
+ * JS mirror of `Query`; word lists are comma-separated, as typed in the search form.
+ */
+export class Query {
+    free(): void;
+    [Symbol.dispose](): void;
+    constructor(seed: string);
+    get bigrams(): Bigrams | undefined;
+    set bigrams(value: Bigrams | null | undefined);
+    excludes: string;
+    includes: string;
+    get max_nodes(): number | undefined;
+    set max_nodes(value: number | null | undefined);
+    max_num_words: number;
+    get max_results(): number | undefined;
+    set max_results(value: number | null | undefined);
+    min_length: number;
+    scorer: string;
+    seed: string;
+    get timeout_ms(): number | undefined;
+    set timeout_ms(value: number | null | undefined);
+    top_n: number;
+}
+
+export class ResultsStruct {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    anagrams: Anagram[];
+    partials: string[];
+    get truncated(): string | undefined;
+    set truncated(value: string | null | undefined);
+}
+
+export function builtin_dictionary_size(): DictionarySize;
+
+export function js_generate(seed: string, min_length: number, max_num_words: number, excludes: string, includes: string, top_n: number): ResultsStruct;
+
 export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
 
 export interface InitOutput {
-  readonly memory: WebAssembly.Memory;
-  readonly __wbg_resultsstruct_free: (a: number) => void;
-  readonly __wbg_get_resultsstruct_anagrams: (a: number) => number;
-  readonly __wbg_set_resultsstruct_anagrams: (a: number, b: number) => void;
-  readonly __wbg_get_resultsstruct_partials: (a: number) => number;
-  readonly __wbg_set_resultsstruct_partials: (a: number, b: number) => void;
-  readonly js_generate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number) => number;
-  readonly __wbindgen_malloc: (a: number) => number;
-  readonly __wbindgen_realloc: (a: number, b: number, c: number) => number;
-  readonly __wbindgen_free: (a: number, b: number) => void;
-}
-
-/**
-* If `module_or_path` is {RequestInfo} or {URL}, makes a request and
-* for everything else, calls `WebAssembly.instantiate` directly.
-*
-* @param {InitInput | Promise<InitInput>} module_or_path
-*
-* @returns {Promise<InitOutput>}
-*/
-export default function init (module_or_path?: InitInput | Promise<InitInput>): Promise<InitOutput>;
+    readonly memory: WebAssembly.Memory;
+    readonly __wbg_anagram_free: (a: number, b: number) => void;
+    readonly __wbg_anagramsolver_free: (a: number, b: number) => void;
+    readonly __wbg_anagramstream_free: (a: number, b: number) => void;
+    readonly __wbg_bigrams_free: (a: number, b: number) => void;
+    readonly __wbg_dictionarysize_free: (a: number, b: number) => void;
+    readonly __wbg_get_anagram_letters_used: (a: number) => number;
+    readonly __wbg_get_anagram_per_word_counts: (a: number) => [number, number];
+    readonly __wbg_get_anagram_score: (a: number) => number;
+    readonly __wbg_get_anagram_words: (a: number) => [number, number];
+    readonly __wbg_get_dictionarysize_compressed: (a: number) => number;
+    readonly __wbg_get_dictionarysize_uncompressed: (a: number) => number;
+    readonly __wbg_get_dictionarysize_word_list: (a: number) => number;
+    readonly __wbg_get_query_bigrams: (a: number) => number;
+    readonly __wbg_get_query_excludes: (a: number) => [number, number];
+    readonly __wbg_get_query_includes: (a: number) => [number, number];
+    readonly __wbg_get_query_max_nodes: (a: number) => [number, number];
+    readonly __wbg_get_query_max_num_words: (a: number) => number;
+    readonly __wbg_get_query_max_results: (a: number) => number;
+    readonly __wbg_get_query_min_length: (a: number) => number;
+    readonly __wbg_get_query_scorer: (a: number) => [number, number];
+    readonly __wbg_get_query_seed: (a: number) => [number, number];
+    readonly __wbg_get_query_timeout_ms: (a: number) => [number, number];
+    readonly __wbg_get_query_top_n: (a: number) => number;
+    readonly __wbg_get_resultsstruct_anagrams: (a: number) => [number, number];
+    readonly __wbg_get_resultsstruct_partials: (a: number) => [number, number];
+    readonly __wbg_get_resultsstruct_truncated: (a: number) => [number, number];
+    readonly __wbg_query_free: (a: number, b: number) => void;
+    readonly __wbg_resultsstruct_free: (a: number, b: number) => void;
+    readonly __wbg_set_anagram_letters_used: (a: number, b: number) => void;
+    readonly __wbg_set_anagram_per_word_counts: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_anagram_score: (a: number, b: number) => void;
+    readonly __wbg_set_anagram_words: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_dictionarysize_compressed: (a: number, b: number) => void;
+    readonly __wbg_set_dictionarysize_uncompressed: (a: number, b: number) => void;
+    readonly __wbg_set_dictionarysize_word_list: (a: number, b: number) => void;
+    readonly __wbg_set_query_bigrams: (a: number, b: number) => void;
+    readonly __wbg_set_query_excludes: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_includes: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_max_nodes: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_max_num_words: (a: number, b: number) => void;
+    readonly __wbg_set_query_max_results: (a: number, b: number) => void;
+    readonly __wbg_set_query_min_length: (a: number, b: number) => void;
+    readonly __wbg_set_query_scorer: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_seed: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_timeout_ms: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_query_top_n: (a: number, b: number) => void;
+    readonly __wbg_set_resultsstruct_anagrams: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_resultsstruct_partials: (a: number, b: number, c: number) => void;
+    readonly __wbg_set_resultsstruct_truncated: (a: number, b: number, c: number) => void;
+    readonly anagramsolver_add_word: (a: number, b: number, c: number, d: number) => [number, number, number];
+    readonly anagramsolver_from_index: (a: number, b: number) => [number, number, number];
+    readonly anagramsolver_from_map: (a: any) => [number, number, number];
+    readonly anagramsolver_from_tsv: (a: number, b: number) => [number, number, number];
+    readonly anagramsolver_from_words: (a: number, b: number) => [number, number, number];
+    readonly anagramsolver_generate: (a: number, b: number) => [number, number, number];
+    readonly anagramsolver_generate_best: (a: number, b: number, c: number) => [number, number, number];
+    readonly anagramsolver_new: () => number;
+    readonly anagramsolver_page: (a: number, b: number, c: number, d: number) => [number, number, number];
+    readonly anagramsolver_remove_word: (a: number, b: number, c: number) => number;
+    readonly anagramsolver_resume: (a: number, b: number, c: number, d: number) => [number, number, number];
+    readonly anagramsolver_sample: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
+    readonly anagramsolver_stream: (a: number, b: number) => [number, number, number];
+    readonly anagramsolver_to_index: (a: number) => [number, number];
+    readonly anagramstream_cancel: (a: number) => void;
+    readonly anagramstream_next_batch: (a: number, b: number) => [number, number];
+    readonly anagramstream_partials: (a: number) => [number, number];
+    readonly anagramstream_resume_token: (a: number) => [number, number];
+    readonly anagramstream_truncated: (a: number) => [number, number];
+    readonly bigrams_from_tsv: (a: number, b: number) => [number, number, number];
+    readonly builtin_dictionary_size: () => number;
+    readonly js_generate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number) => [number, number, number];
+    readonly query_new: (a: number, b: number) => number;
+    readonly wasm_bindgen__convert__closures_____invoke__h05375c079e383286: (a: number, b: number, c: any, d: any) => void;
+    readonly __wbindgen_malloc: (a: number, b: number) => number;
+    readonly __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
+    readonly __wbindgen_free: (a: number, b: number, c: number) => void;
+    readonly __wbindgen_externrefs: WebAssembly.Table;
+    readonly __externref_table_dealloc: (a: number) => void;
+    readonly __externref_table_alloc: () => number;
+    readonly __externref_drop_slice: (a: number, b: number) => void;
+    readonly __wbindgen_start: () => void;
+}
+
+export type SyncInitInput = BufferSource | WebAssembly.Module;
+
+/**
+ * Instantiates the given `module`, which can either be bytes or
+ * a precompiled `WebAssembly.Module`.
+ *
+ * @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
+ *
+ * @returns {InitOutput}
+ */
+export function initSync(module: { module: SyncInitInput } | SyncInitInput): InitOutput;
+
+/**
+ * If `module_or_path` is {RequestInfo} or {URL}, makes a request and
+ * for everything else, calls `WebAssembly.instantiate` directly.
+ *
+ * @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
+ *
+ * @returns {Promise<InitOutput>}
+ */
+export default function __wbg_init (module_or_path?: { module_or_path: InitInput | Promise<InitInput> } | InitInput | Promise<InitInput>): Promise<InitOutput>;
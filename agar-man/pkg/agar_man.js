@@ -1,82 +1,1016 @@
+/* @ts-self-types="./agar_man.d.ts" */
 
-let wasm;
+/**
+ * A single anagram, with the frequency information used to rank it.
+ */
+export class Anagram {
+    static __wrap(ptr) {
+        const obj = Object.create(Anagram.prototype);
+        obj.__wbg_ptr = ptr;
+        AnagramFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    static __unwrap(jsValue) {
+        if (!(jsValue instanceof Anagram)) {
+            return 0;
+        }
+        return jsValue.__destroy_into_raw();
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        AnagramFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_anagram_free(ptr, 0);
+    }
+    /**
+     * @returns {number}
+     */
+    get letters_used() {
+        const ret = wasm.__wbg_get_anagram_letters_used(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {Uint32Array}
+     */
+    get per_word_counts() {
+        const ret = wasm.__wbg_get_anagram_per_word_counts(this.__wbg_ptr);
+        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {number}
+     */
+    get score() {
+        const ret = wasm.__wbg_get_anagram_score(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {string[]}
+     */
+    get words() {
+        const ret = wasm.__wbg_get_anagram_words(this.__wbg_ptr);
+        var v1 = getArrayJsValueFromWasm0(ret[0], ret[1]);
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set letters_used(arg0) {
+        wasm.__wbg_set_anagram_letters_used(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {Uint32Array} arg0
+     */
+    set per_word_counts(arg0) {
+        const ptr0 = passArray32ToWasm0(arg0, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_anagram_per_word_counts(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set score(arg0) {
+        wasm.__wbg_set_anagram_score(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {string[]} arg0
+     */
+    set words(arg0) {
+        const ptr0 = passArrayJsValueToWasm0(arg0, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_anagram_words(this.__wbg_ptr, ptr0, len0);
+    }
+}
+if (Symbol.dispose) Anagram.prototype[Symbol.dispose] = Anagram.prototype.free;
+
+/**
+ * JS handle to an `AnagramSolver`; construct it once and call `generate` for every `Query`.
+ */
+export class AnagramSolver {
+    static __wrap(ptr) {
+        const obj = Object.create(AnagramSolver.prototype);
+        obj.__wbg_ptr = ptr;
+        AnagramSolverFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        AnagramSolverFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_anagramsolver_free(ptr, 0);
+    }
+    /**
+     * Add a name, brand or other word missing from the dictionary; see `add_word`.
+     * @param {string} word
+     * @param {number | null} [count]
+     * @returns {boolean}
+     */
+    add_word(word, count) {
+        const ptr0 = passStringToWasm0(word, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_add_word(this.__wbg_ptr, ptr0, len0, isLikeNone(count) ? Number.MAX_SAFE_INTEGER : (count) >>> 0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
+    }
+    /**
+     * Solver over an index saved with `to_index`.
+     * @param {Uint8Array} bytes
+     * @returns {AnagramSolver}
+     */
+    static from_index(bytes) {
+        const ptr0 = passArray8ToWasm0(bytes, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_from_index(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramSolver.__wrap(ret[0]);
+    }
+    /**
+     * Solver over a `Map` from words to frequency counts.
+     * @param {Map<any, any>} map
+     * @returns {AnagramSolver}
+     */
+    static from_map(map) {
+        const ret = wasm.anagramsolver_from_map(map);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramSolver.__wrap(ret[0]);
+    }
+    /**
+     * Solver over `word\tcount` lines, e.g. the text of a fetched file.
+     * @param {string} text
+     * @returns {AnagramSolver}
+     */
+    static from_tsv(text) {
+        const ptr0 = passStringToWasm0(text, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_from_tsv(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramSolver.__wrap(ret[0]);
+    }
+    /**
+     * Solver over a list of words, ranked in the order given.
+     * @param {string[]} words
+     * @returns {AnagramSolver}
+     */
+    static from_words(words) {
+        const ptr0 = passArrayJsValueToWasm0(words, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_from_words(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramSolver.__wrap(ret[0]);
+    }
+    /**
+     * @param {Query} query
+     * @returns {ResultsStruct}
+     */
+    generate(query) {
+        _assertClass(query, Query);
+        const ret = wasm.anagramsolver_generate(this.__wbg_ptr, query.__wbg_ptr);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ResultsStruct.__wrap(ret[0]);
+    }
+    /**
+     * Only the `k` best anagrams of a query, without enumerating the rest; see `solve_best`.
+     * @param {Query} query
+     * @param {number} k
+     * @returns {ResultsStruct}
+     */
+    generate_best(query, k) {
+        _assertClass(query, Query);
+        const ret = wasm.anagramsolver_generate_best(this.__wbg_ptr, query.__wbg_ptr, k);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ResultsStruct.__wrap(ret[0]);
+    }
+    constructor() {
+        const ret = wasm.anagramsolver_new();
+        this.__wbg_ptr = ret;
+        AnagramSolverFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    /**
+     * The anagrams from `offset` to `offset + limit` by score; see `AnagramSolver::solve_page`.
+     * @param {Query} query
+     * @param {number} offset
+     * @param {number} limit
+     * @returns {ResultsStruct}
+     */
+    page(query, offset, limit) {
+        _assertClass(query, Query);
+        const ret = wasm.anagramsolver_page(this.__wbg_ptr, query.__wbg_ptr, offset, limit);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ResultsStruct.__wrap(ret[0]);
+    }
+    /**
+     * @param {string} word
+     * @returns {boolean}
+     */
+    remove_word(word) {
+        const ptr0 = passStringToWasm0(word, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_remove_word(this.__wbg_ptr, ptr0, len0);
+        return ret !== 0;
+    }
+    /**
+     * Carry on from a stream's `resume_token`, e.g. to load more without keeping the stream.
+     * @param {Query} query
+     * @param {string} token
+     * @returns {AnagramStream}
+     */
+    resume(query, token) {
+        _assertClass(query, Query);
+        const ptr0 = passStringToWasm0(token, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.anagramsolver_resume(this.__wbg_ptr, query.__wbg_ptr, ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramStream.__wrap(ret[0]);
+    }
+    /**
+     * Draw random anagrams, favouring common words if `weighted`; seeds must be below 2^53.
+     * @param {Query} query
+     * @param {number} num_samples
+     * @param {boolean} weighted
+     * @param {number} seed
+     * @returns {ResultsStruct}
+     */
+    sample(query, num_samples, weighted, seed) {
+        _assertClass(query, Query);
+        const ret = wasm.anagramsolver_sample(this.__wbg_ptr, query.__wbg_ptr, num_samples, weighted, seed);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ResultsStruct.__wrap(ret[0]);
+    }
+    /**
+     * Start a lazy search; pull results with `next_batch` instead of waiting for all of them.
+     * @param {Query} query
+     * @returns {AnagramStream}
+     */
+    stream(query) {
+        _assertClass(query, Query);
+        const ret = wasm.anagramsolver_stream(this.__wbg_ptr, query.__wbg_ptr);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return AnagramStream.__wrap(ret[0]);
+    }
+    /**
+     * @returns {Uint8Array}
+     */
+    to_index() {
+        const ret = wasm.anagramsolver_to_index(this.__wbg_ptr);
+        var v1 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        return v1;
+    }
+}
+if (Symbol.dispose) AnagramSolver.prototype[Symbol.dispose] = AnagramSolver.prototype.free;
+
+/**
+ * JS handle to an in-progress search, returned by `AnagramSolver.stream`.
+ */
+export class AnagramStream {
+    static __wrap(ptr) {
+        const obj = Object.create(AnagramStream.prototype);
+        obj.__wbg_ptr = ptr;
+        AnagramStreamFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        AnagramStreamFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_anagramstream_free(ptr, 0);
+    }
+    /**
+     * Stop the search; later batches come back empty.
+     */
+    cancel() {
+        wasm.anagramstream_cancel(this.__wbg_ptr);
+    }
+    /**
+     * Up to `n` more anagrams, in search order; an empty array means the search is done.
+     * @param {number} n
+     * @returns {Anagram[]}
+     */
+    next_batch(n) {
+        const ret = wasm.anagramstream_next_batch(this.__wbg_ptr, n);
+        var v1 = getArrayJsValueFromWasm0(ret[0], ret[1]);
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * Every word that fits in the seed.
+     * @returns {string[]}
+     */
+    partials() {
+        const ret = wasm.anagramstream_partials(this.__wbg_ptr);
+        var v1 = getArrayJsValueFromWasm0(ret[0], ret[1]);
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * A token for `AnagramSolver.resume`, or undefined once the search is done.
+     * @returns {string | undefined}
+     */
+    resume_token() {
+        const ret = wasm.anagramstream_resume_token(this.__wbg_ptr);
+        let v1;
+        if (ret[0] !== 0) {
+            v1 = getStringFromWasm0(ret[0], ret[1]);
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v1;
+    }
+    /**
+     * Why the search stopped early, if it did.
+     * @returns {string | undefined}
+     */
+    truncated() {
+        const ret = wasm.anagramstream_truncated(this.__wbg_ptr);
+        let v1;
+        if (ret[0] !== 0) {
+            v1 = getStringFromWasm0(ret[0], ret[1]);
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v1;
+    }
+}
+if (Symbol.dispose) AnagramStream.prototype[Symbol.dispose] = AnagramStream.prototype.free;
 
-const heap = new Array(32).fill(undefined);
+/**
+ * JS handle to a `Bigrams` table, shared by every `Query` it is set on.
+ */
+export class Bigrams {
+    static __wrap(ptr) {
+        const obj = Object.create(Bigrams.prototype);
+        obj.__wbg_ptr = ptr;
+        BigramsFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        BigramsFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_bigrams_free(ptr, 0);
+    }
+    /**
+     * A table from `first\tsecond\tcount` lines, e.g. the text of a fetched file.
+     * @param {string} text
+     * @returns {Bigrams}
+     */
+    static from_tsv(text) {
+        const ptr0 = passStringToWasm0(text, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.bigrams_from_tsv(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return Bigrams.__wrap(ret[0]);
+    }
+}
+if (Symbol.dispose) Bigrams.prototype[Symbol.dispose] = Bigrams.prototype.free;
 
-heap.push(undefined, null, true, false);
+/**
+ * How many bytes the built-in dictionary adds to the binary or wasm bundle.
+ */
+export class DictionarySize {
+    static __wrap(ptr) {
+        const obj = Object.create(DictionarySize.prototype);
+        obj.__wbg_ptr = ptr;
+        DictionarySizeFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        DictionarySizeFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_dictionarysize_free(ptr, 0);
+    }
+    /**
+     * @returns {number}
+     */
+    get compressed() {
+        const ret = wasm.__wbg_get_dictionarysize_compressed(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get uncompressed() {
+        const ret = wasm.__wbg_get_dictionarysize_uncompressed(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get word_list() {
+        const ret = wasm.__wbg_get_dictionarysize_word_list(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} arg0
+     */
+    set compressed(arg0) {
+        wasm.__wbg_set_dictionarysize_compressed(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set uncompressed(arg0) {
+        wasm.__wbg_set_dictionarysize_uncompressed(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set word_list(arg0) {
+        wasm.__wbg_set_dictionarysize_word_list(this.__wbg_ptr, arg0);
+    }
+}
+if (Symbol.dispose) DictionarySize.prototype[Symbol.dispose] = DictionarySize.prototype.free;
+
+/**
+ * JS mirror of `Query`; word lists are comma-separated, as typed in the search form.
+ */
+export class Query {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        QueryFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_query_free(ptr, 0);
+    }
+    /**
+     * @returns {Bigrams | undefined}
+     */
+    get bigrams() {
+        const ret = wasm.__wbg_get_query_bigrams(this.__wbg_ptr);
+        return ret === 0 ? undefined : Bigrams.__wrap(ret);
+    }
+    /**
+     * @returns {string}
+     */
+    get excludes() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.__wbg_get_query_excludes(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
+    /**
+     * @returns {string}
+     */
+    get includes() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.__wbg_get_query_includes(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
+    /**
+     * @returns {number | undefined}
+     */
+    get max_nodes() {
+        const ret = wasm.__wbg_get_query_max_nodes(this.__wbg_ptr);
+        return ret[0] === 0 ? undefined : ret[1];
+    }
+    /**
+     * @returns {number}
+     */
+    get max_num_words() {
+        const ret = wasm.__wbg_get_query_max_num_words(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {number | undefined}
+     */
+    get max_results() {
+        const ret = wasm.__wbg_get_query_max_results(this.__wbg_ptr);
+        return ret === Number.MAX_SAFE_INTEGER ? undefined : ret;
+    }
+    /**
+     * @returns {number}
+     */
+    get min_length() {
+        const ret = wasm.__wbg_get_query_min_length(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @returns {string}
+     */
+    get scorer() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.__wbg_get_query_scorer(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
+    /**
+     * @returns {string}
+     */
+    get seed() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.__wbg_get_query_seed(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
+    /**
+     * @returns {number | undefined}
+     */
+    get timeout_ms() {
+        const ret = wasm.__wbg_get_query_timeout_ms(this.__wbg_ptr);
+        return ret[0] === 0 ? undefined : ret[1];
+    }
+    /**
+     * @returns {number}
+     */
+    get top_n() {
+        const ret = wasm.__wbg_get_query_top_n(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {string} seed
+     */
+    constructor(seed) {
+        const ptr0 = passStringToWasm0(seed, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.query_new(ptr0, len0);
+        this.__wbg_ptr = ret;
+        QueryFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    /**
+     * @param {Bigrams | null} [arg0]
+     */
+    set bigrams(arg0) {
+        let ptr0 = 0;
+        if (!isLikeNone(arg0)) {
+            _assertClass(arg0, Bigrams);
+            ptr0 = arg0.__destroy_into_raw();
+        }
+        wasm.__wbg_set_query_bigrams(this.__wbg_ptr, ptr0);
+    }
+    /**
+     * @param {string} arg0
+     */
+    set excludes(arg0) {
+        const ptr0 = passStringToWasm0(arg0, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_query_excludes(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {string} arg0
+     */
+    set includes(arg0) {
+        const ptr0 = passStringToWasm0(arg0, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_query_includes(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {number | null} [arg0]
+     */
+    set max_nodes(arg0) {
+        wasm.__wbg_set_query_max_nodes(this.__wbg_ptr, !isLikeNone(arg0), isLikeNone(arg0) ? 0 : arg0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set max_num_words(arg0) {
+        wasm.__wbg_set_query_max_num_words(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {number | null} [arg0]
+     */
+    set max_results(arg0) {
+        wasm.__wbg_set_query_max_results(this.__wbg_ptr, isLikeNone(arg0) ? Number.MAX_SAFE_INTEGER : (arg0) >>> 0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set min_length(arg0) {
+        wasm.__wbg_set_query_min_length(this.__wbg_ptr, arg0);
+    }
+    /**
+     * @param {string} arg0
+     */
+    set scorer(arg0) {
+        const ptr0 = passStringToWasm0(arg0, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_query_scorer(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {string} arg0
+     */
+    set seed(arg0) {
+        const ptr0 = passStringToWasm0(arg0, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_query_seed(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {number | null} [arg0]
+     */
+    set timeout_ms(arg0) {
+        wasm.__wbg_set_query_timeout_ms(this.__wbg_ptr, !isLikeNone(arg0), isLikeNone(arg0) ? 0 : arg0);
+    }
+    /**
+     * @param {number} arg0
+     */
+    set top_n(arg0) {
+        wasm.__wbg_set_query_top_n(this.__wbg_ptr, arg0);
+    }
+}
+if (Symbol.dispose) Query.prototype[Symbol.dispose] = Query.prototype.free;
 
-function getObject(idx) { return heap[idx]; }
+export class ResultsStruct {
+    static __wrap(ptr) {
+        const obj = Object.create(ResultsStruct.prototype);
+        obj.__wbg_ptr = ptr;
+        ResultsStructFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        ResultsStructFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_resultsstruct_free(ptr, 0);
+    }
+    /**
+     * @returns {Anagram[]}
+     */
+    get anagrams() {
+        const ret = wasm.__wbg_get_resultsstruct_anagrams(this.__wbg_ptr);
+        var v1 = getArrayJsValueFromWasm0(ret[0], ret[1]);
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {string[]}
+     */
+    get partials() {
+        const ret = wasm.__wbg_get_resultsstruct_partials(this.__wbg_ptr);
+        var v1 = getArrayJsValueFromWasm0(ret[0], ret[1]);
+        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+        return v1;
+    }
+    /**
+     * @returns {string | undefined}
+     */
+    get truncated() {
+        const ret = wasm.__wbg_get_resultsstruct_truncated(this.__wbg_ptr);
+        let v1;
+        if (ret[0] !== 0) {
+            v1 = getStringFromWasm0(ret[0], ret[1]);
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v1;
+    }
+    /**
+     * @param {Anagram[]} arg0
+     */
+    set anagrams(arg0) {
+        const ptr0 = passArrayJsValueToWasm0(arg0, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_resultsstruct_anagrams(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {string[]} arg0
+     */
+    set partials(arg0) {
+        const ptr0 = passArrayJsValueToWasm0(arg0, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_resultsstruct_partials(this.__wbg_ptr, ptr0, len0);
+    }
+    /**
+     * @param {string | null} [arg0]
+     */
+    set truncated(arg0) {
+        var ptr0 = isLikeNone(arg0) ? 0 : passStringToWasm0(arg0, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        var len0 = WASM_VECTOR_LEN;
+        wasm.__wbg_set_resultsstruct_truncated(this.__wbg_ptr, ptr0, len0);
+    }
+}
+if (Symbol.dispose) ResultsStruct.prototype[Symbol.dispose] = ResultsStruct.prototype.free;
 
-let heap_next = heap.length;
+/**
+ * @returns {DictionarySize}
+ */
+export function builtin_dictionary_size() {
+    const ret = wasm.builtin_dictionary_size();
+    return DictionarySize.__wrap(ret);
+}
 
-function dropObject(idx) {
-    if (idx < 36) return;
-    heap[idx] = heap_next;
-    heap_next = idx;
+/**
+ * @param {string} seed
+ * @param {number} min_length
+ * @param {number} max_num_words
+ * @param {string} excludes
+ * @param {string} includes
+ * @param {number} top_n
+ * @returns {ResultsStruct}
+ */
+export function js_generate(seed, min_length, max_num_words, excludes, includes, top_n) {
+    const ptr0 = passStringToWasm0(seed, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passStringToWasm0(excludes, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passStringToWasm0(includes, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ret = wasm.js_generate(ptr0, len0, min_length, max_num_words, ptr1, len1, ptr2, len2, top_n);
+    if (ret[2]) {
+        throw takeFromExternrefTable0(ret[1]);
+    }
+    return ResultsStruct.__wrap(ret[0]);
+}
+function __wbg_get_imports() {
+    const import0 = {
+        __proto__: null,
+        __wbg___wbindgen_number_get_2e0e7dee9f701a71: function(arg0, arg1) {
+            const obj = arg1;
+            const ret = typeof(obj) === 'number' ? obj : undefined;
+            getDataViewMemory0().setFloat64(arg0 + 8 * 1, isLikeNone(ret) ? 0 : ret, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, !isLikeNone(ret), true);
+        },
+        __wbg___wbindgen_string_get_0380ccaa2f57f0d9: function(arg0, arg1) {
+            const obj = arg1;
+            const ret = typeof(obj) === 'string' ? obj : undefined;
+            var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            var len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
+        __wbg___wbindgen_throw_41e9ee4f547fc59a: function(arg0, arg1) {
+            throw new Error(getStringFromWasm0(arg0, arg1));
+        },
+        __wbg_anagram_new: function(arg0) {
+            const ret = Anagram.__wrap(arg0);
+            return ret;
+        },
+        __wbg_anagram_unwrap: function(arg0) {
+            const ret = Anagram.__unwrap(arg0);
+            return ret;
+        },
+        __wbg_error_757e9472f8410341: function(arg0, arg1) {
+            let deferred0_0;
+            let deferred0_1;
+            try {
+                deferred0_0 = arg0;
+                deferred0_1 = arg1;
+                console.error(getStringFromWasm0(arg0, arg1));
+            } finally {
+                wasm.__wbindgen_free(deferred0_0, deferred0_1, 1);
+            }
+        },
+        __wbg_forEach_7ad975c8e42636ed: function(arg0, arg1, arg2) {
+            try {
+                var state0 = {a: arg1, b: arg2};
+                var cb0 = (arg0, arg1) => {
+                    const a = state0.a;
+                    state0.a = 0;
+                    try {
+                        return wasm_bindgen__convert__closures_____invoke__h05375c079e383286(a, state0.b, arg0, arg1);
+                    } finally {
+                        state0.a = a;
+                    }
+                };
+                arg0.forEach(cb0);
+            } finally {
+                state0.a = 0;
+            }
+        },
+        __wbg_new_227d7c05414eb861: function() {
+            const ret = new Error();
+            return ret;
+        },
+        __wbg_new_343a093a3c2ffb4e: function(arg0, arg1) {
+            const ret = new Error(getStringFromWasm0(arg0, arg1));
+            return ret;
+        },
+        __wbg_now_aa4ccb83129e9e55: function() {
+            const ret = Date.now();
+            return ret;
+        },
+        __wbg_stack_3b0d974bbf31e44f: function(arg0, arg1) {
+            const ret = arg1.stack;
+            const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
+        __wbindgen_generic_0000000000000001: function(arg0, arg1) {
+            // Cast intrinsic for `Ref(String) -> Externref`.
+            const ret = getStringFromWasm0(arg0, arg1);
+            return ret;
+        },
+        __wbindgen_init_externref_table: function() {
+            const table = wasm.__wbindgen_externrefs;
+            const offset = table.grow(4);
+            table.set(0, undefined);
+            table.set(offset + 0, undefined);
+            table.set(offset + 1, null);
+            table.set(offset + 2, true);
+            table.set(offset + 3, false);
+        },
+    };
+    return {
+        __proto__: null,
+        "./agar_man_bg.js": import0,
+    };
 }
 
-function takeObject(idx) {
-    const ret = getObject(idx);
-    dropObject(idx);
-    return ret;
+function wasm_bindgen__convert__closures_____invoke__h05375c079e383286(arg0, arg1, arg2, arg3) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h05375c079e383286(arg0, arg1, arg2, arg3);
 }
 
-function addHeapObject(obj) {
-    if (heap_next === heap.length) heap.push(heap.length + 1);
-    const idx = heap_next;
-    heap_next = heap[idx];
+const AnagramFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_anagram_free(ptr, 1));
+const AnagramSolverFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_anagramsolver_free(ptr, 1));
+const AnagramStreamFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_anagramstream_free(ptr, 1));
+const BigramsFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_bigrams_free(ptr, 1));
+const DictionarySizeFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_dictionarysize_free(ptr, 1));
+const QueryFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_query_free(ptr, 1));
+const ResultsStructFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_resultsstruct_free(ptr, 1));
 
-    heap[idx] = obj;
+function addToExternrefTable0(obj) {
+    const idx = wasm.__externref_table_alloc();
+    wasm.__wbindgen_externrefs.set(idx, obj);
     return idx;
 }
 
-let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+function _assertClass(instance, klass) {
+    if (!(instance instanceof klass)) {
+        throw new Error(`expected instance of ${klass.name}`);
+    }
+}
 
-cachedTextDecoder.decode();
+function getArrayJsValueFromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    const mem = getDataViewMemory0();
+    const result = [];
+    for (let i = ptr; i < ptr + 4 * len; i += 4) {
+        result.push(wasm.__wbindgen_externrefs.get(mem.getUint32(i, true)));
+    }
+    wasm.__externref_drop_slice(ptr, len);
+    return result;
+}
+
+function getArrayU32FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getUint32ArrayMemory0().subarray(ptr / 4, ptr / 4 + len);
+}
+
+function getArrayU8FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getUint8ArrayMemory0().subarray(ptr / 1, ptr / 1 + len);
+}
 
-let cachegetUint8Memory0 = null;
-function getUint8Memory0() {
-    if (cachegetUint8Memory0 === null || cachegetUint8Memory0.buffer !== wasm.memory.buffer) {
-        cachegetUint8Memory0 = new Uint8Array(wasm.memory.buffer);
+let cachedDataViewMemory0 = null;
+function getDataViewMemory0() {
+    if (cachedDataViewMemory0 === null || cachedDataViewMemory0.buffer.detached === true || (cachedDataViewMemory0.buffer.detached === undefined && cachedDataViewMemory0.buffer !== wasm.memory.buffer)) {
+        cachedDataViewMemory0 = new DataView(wasm.memory.buffer);
     }
-    return cachegetUint8Memory0;
+    return cachedDataViewMemory0;
 }
 
 function getStringFromWasm0(ptr, len) {
-    return cachedTextDecoder.decode(getUint8Memory0().subarray(ptr, ptr + len));
+    return decodeText(ptr >>> 0, len);
 }
 
-let WASM_VECTOR_LEN = 0;
+let cachedUint32ArrayMemory0 = null;
+function getUint32ArrayMemory0() {
+    if (cachedUint32ArrayMemory0 === null || cachedUint32ArrayMemory0.byteLength === 0) {
+        cachedUint32ArrayMemory0 = new Uint32Array(wasm.memory.buffer);
+    }
+    return cachedUint32ArrayMemory0;
+}
+
+let cachedUint8ArrayMemory0 = null;
+function getUint8ArrayMemory0() {
+    if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
+        cachedUint8ArrayMemory0 = new Uint8Array(wasm.memory.buffer);
+    }
+    return cachedUint8ArrayMemory0;
+}
 
-let cachedTextEncoder = new TextEncoder('utf-8');
+function isLikeNone(x) {
+    return x === undefined || x === null;
+}
 
-const encodeString = (typeof cachedTextEncoder.encodeInto === 'function'
-    ? function (arg, view) {
-    return cachedTextEncoder.encodeInto(arg, view);
+function passArray32ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 4, 4) >>> 0;
+    getUint32ArrayMemory0().set(arg, ptr / 4);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
 }
-    : function (arg, view) {
-    const buf = cachedTextEncoder.encode(arg);
-    view.set(buf);
-    return {
-        read: arg.length,
-        written: buf.length
-    };
-});
 
-function passStringToWasm0(arg, malloc, realloc) {
+function passArray8ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 1, 1) >>> 0;
+    getUint8ArrayMemory0().set(arg, ptr / 1);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+function passArrayJsValueToWasm0(array, malloc) {
+    const ptr = malloc(array.length * 4, 4) >>> 0;
+    for (let i = 0; i < array.length; i++) {
+        const add = addToExternrefTable0(array[i]);
+        getDataViewMemory0().setUint32(ptr + 4 * i, add, true);
+    }
+    WASM_VECTOR_LEN = array.length;
+    return ptr;
+}
 
+function passStringToWasm0(arg, malloc, realloc) {
     if (realloc === undefined) {
         const buf = cachedTextEncoder.encode(arg);
-        const ptr = malloc(buf.length);
-        getUint8Memory0().subarray(ptr, ptr + buf.length).set(buf);
+        const ptr = malloc(buf.length, 1) >>> 0;
+        getUint8ArrayMemory0().subarray(ptr, ptr + buf.length).set(buf);
         WASM_VECTOR_LEN = buf.length;
         return ptr;
     }
 
     let len = arg.length;
-    let ptr = malloc(len);
+    let ptr = malloc(len, 1) >>> 0;
 
-    const mem = getUint8Memory0();
+    const mem = getUint8ArrayMemory0();
 
     let offset = 0;
 
@@ -85,190 +1019,152 @@ function passStringToWasm0(arg, malloc, realloc) {
         if (code > 0x7F) break;
         mem[ptr + offset] = code;
     }
-
     if (offset !== len) {
         if (offset !== 0) {
             arg = arg.slice(offset);
         }
-        ptr = realloc(ptr, len, len = offset + arg.length * 3);
-        const view = getUint8Memory0().subarray(ptr + offset, ptr + len);
-        const ret = encodeString(arg, view);
+        ptr = realloc(ptr, len, len = offset + arg.length * 3, 1) >>> 0;
+        const view = getUint8ArrayMemory0().subarray(ptr + offset, ptr + len);
+        const ret = cachedTextEncoder.encodeInto(arg, view);
 
         offset += ret.written;
+        ptr = realloc(ptr, len, offset, 1) >>> 0;
     }
 
     WASM_VECTOR_LEN = offset;
     return ptr;
 }
-/**
-* @param {string} seed
-* @param {number} min_length
-* @param {number} max_num_words
-* @param {string} excludes
-* @param {string} includes
-* @param {number} top_n
-* @returns {ResultsStruct}
-*/
-export function js_generate(seed, min_length, max_num_words, excludes, includes, top_n) {
-    var ptr0 = passStringToWasm0(seed, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-    var len0 = WASM_VECTOR_LEN;
-    var ptr1 = passStringToWasm0(excludes, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-    var len1 = WASM_VECTOR_LEN;
-    var ptr2 = passStringToWasm0(includes, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-    var len2 = WASM_VECTOR_LEN;
-    var ret = wasm.js_generate(ptr0, len0, min_length, max_num_words, ptr1, len1, ptr2, len2, top_n);
-    return ResultsStruct.__wrap(ret);
+
+function takeFromExternrefTable0(idx) {
+    const value = wasm.__wbindgen_externrefs.get(idx);
+    wasm.__externref_table_dealloc(idx);
+    return value;
 }
 
-let cachegetInt32Memory0 = null;
-function getInt32Memory0() {
-    if (cachegetInt32Memory0 === null || cachegetInt32Memory0.buffer !== wasm.memory.buffer) {
-        cachegetInt32Memory0 = new Int32Array(wasm.memory.buffer);
+let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+cachedTextDecoder.decode();
+const MAX_SAFARI_DECODE_BYTES = 2146435072;
+let numBytesDecoded = 0;
+function decodeText(ptr, len) {
+    numBytesDecoded += len;
+    if (numBytesDecoded >= MAX_SAFARI_DECODE_BYTES) {
+        cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+        cachedTextDecoder.decode();
+        numBytesDecoded = len;
     }
-    return cachegetInt32Memory0;
+    return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
 }
-/**
-*/
-export class ResultsStruct {
 
-    static __wrap(ptr) {
-        const obj = Object.create(ResultsStruct.prototype);
-        obj.ptr = ptr;
+const cachedTextEncoder = new TextEncoder();
 
-        return obj;
-    }
-
-    __destroy_into_raw() {
-        const ptr = this.ptr;
-        this.ptr = 0;
+if (!('encodeInto' in cachedTextEncoder)) {
+    cachedTextEncoder.encodeInto = function (arg, view) {
+        const buf = cachedTextEncoder.encode(arg);
+        view.set(buf);
+        return {
+            read: arg.length,
+            written: buf.length
+        };
+    };
+}
 
-        return ptr;
-    }
+let WASM_VECTOR_LEN = 0;
 
-    free() {
-        const ptr = this.__destroy_into_raw();
-        wasm.__wbg_resultsstruct_free(ptr);
-    }
-    /**
-    */
-    get anagrams() {
-        var ret = wasm.__wbg_get_resultsstruct_anagrams(this.ptr);
-        return takeObject(ret);
-    }
-    /**
-    * @param {Array<any>} arg0
-    */
-    set anagrams(arg0) {
-        wasm.__wbg_set_resultsstruct_anagrams(this.ptr, addHeapObject(arg0));
-    }
-    /**
-    */
-    get partials() {
-        var ret = wasm.__wbg_get_resultsstruct_partials(this.ptr);
-        return takeObject(ret);
-    }
-    /**
-    * @param {Array<any>} arg0
-    */
-    set partials(arg0) {
-        wasm.__wbg_set_resultsstruct_partials(this.ptr, addHeapObject(arg0));
-    }
+let wasmModule, wasmInstance, wasm;
+function __wbg_finalize_init(instance, module) {
+    wasmInstance = instance;
+    wasm = instance.exports;
+    wasmModule = module;
+    cachedDataViewMemory0 = null;
+    cachedUint32ArrayMemory0 = null;
+    cachedUint8ArrayMemory0 = null;
+    wasm.__wbindgen_start();
+    return wasm;
 }
 
-async function load(module, imports) {
+async function __wbg_load(module, imports) {
     if (typeof Response === 'function' && module instanceof Response) {
+        if (!module.ok) {
+            throw new Error(`failed to fetch Wasm: ${module.status} ${module.statusText} fetching '${module.url}'`);
+        }
+
         if (typeof WebAssembly.instantiateStreaming === 'function') {
             try {
                 return await WebAssembly.instantiateStreaming(module, imports);
-
             } catch (e) {
-                if (module.headers.get('Content-Type') != 'application/wasm') {
-                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
+                const validResponse = expectedResponseType(module.type);
+
+                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
+                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
 
-                } else {
-                    throw e;
-                }
+                } else { throw e; }
             }
         }
 
         const bytes = await module.arrayBuffer();
         return await WebAssembly.instantiate(bytes, imports);
-
     } else {
         const instance = await WebAssembly.instantiate(module, imports);
 
         if (instance instanceof WebAssembly.Instance) {
             return { instance, module };
-
         } else {
             return instance;
         }
     }
-}
 
-async function init(input) {
-    if (typeof input === 'undefined') {
-        input = new URL('agar_man_bg.wasm', import.meta.url);
+    function expectedResponseType(type) {
+        switch (type) {
+            case 'basic': case 'cors': case 'default': return true;
+        }
+        return false;
     }
-    const imports = {};
-    imports.wbg = {};
-    imports.wbg.__wbindgen_object_drop_ref = function(arg0) {
-        takeObject(arg0);
-    };
-    imports.wbg.__wbindgen_object_clone_ref = function(arg0) {
-        var ret = getObject(arg0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_newwithlength_9c398a17849b31ce = function(arg0) {
-        var ret = new Array(arg0 >>> 0);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_length_7b60f47bde714631 = function(arg0) {
-        var ret = getObject(arg0).length;
-        return ret;
-    };
-    imports.wbg.__wbindgen_string_new = function(arg0, arg1) {
-        var ret = getStringFromWasm0(arg0, arg1);
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_set_a42efa3c7f01c8b1 = function(arg0, arg1, arg2) {
-        getObject(arg0)[arg1 >>> 0] = takeObject(arg2);
-    };
-    imports.wbg.__wbg_new_693216e109162396 = function() {
-        var ret = new Error();
-        return addHeapObject(ret);
-    };
-    imports.wbg.__wbg_stack_0ddaca5d1abfb52f = function(arg0, arg1) {
-        var ret = getObject(arg1).stack;
-        var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-        var len0 = WASM_VECTOR_LEN;
-        getInt32Memory0()[arg0 / 4 + 1] = len0;
-        getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-    };
-    imports.wbg.__wbg_error_09919627ac0992f5 = function(arg0, arg1) {
-        try {
-            console.error(getStringFromWasm0(arg0, arg1));
-        } finally {
-            wasm.__wbindgen_free(arg0, arg1);
+}
+
+function initSync(module) {
+    if (wasm !== undefined) return wasm;
+
+
+    if (module !== undefined) {
+        if (Object.getPrototypeOf(module) === Object.prototype) {
+            ({module} = module)
+        } else {
+            console.warn('using deprecated parameters for `initSync()`; pass a single object instead')
         }
-    };
-    imports.wbg.__wbindgen_throw = function(arg0, arg1) {
-        throw new Error(getStringFromWasm0(arg0, arg1));
-    };
+    }
 
-    if (typeof input === 'string' || (typeof Request === 'function' && input instanceof Request) || (typeof URL === 'function' && input instanceof URL)) {
-        input = fetch(input);
+    const imports = __wbg_get_imports();
+    if (!(module instanceof WebAssembly.Module)) {
+        module = new WebAssembly.Module(module);
     }
+    const instance = new WebAssembly.Instance(module, imports);
+    return __wbg_finalize_init(instance, module);
+}
 
+async function __wbg_init(module_or_path) {
+    if (wasm !== undefined) return wasm;
 
 
-    const { instance, module } = await load(await input, imports);
+    if (module_or_path !== undefined) {
+        if (Object.getPrototypeOf(module_or_path) === Object.prototype) {
+            ({module_or_path} = module_or_path)
+        } else {
+            console.warn('using deprecated parameters for the initialization function; pass a single object instead')
+        }
+    }
 
-    wasm = instance.exports;
-    init.__wbindgen_wasm_module = module;
+    if (module_or_path === undefined) {
+        module_or_path = new URL('agar_man_bg.wasm', import.meta.url);
+    }
+    const imports = __wbg_get_imports();
 
-    return wasm;
-}
+    if (typeof module_or_path === 'string' || (typeof Request === 'function' && module_or_path instanceof Request) || (typeof URL === 'function' && module_or_path instanceof URL)) {
+        module_or_path = fetch(module_or_path);
+    }
 
-export default init;
+    const { instance, module } = await __wbg_load(await module_or_path, imports);
+
+    return __wbg_finalize_init(instance, module);
+}
 
+export { initSync, __wbg_init as default };
@@ -1,12 +1,84 @@
 /* tslint:disable */
 /* eslint-disable */
 export const memory: WebAssembly.Memory;
-export function __wbg_resultsstruct_free(a: number): void;
-export function __wbg_get_resultsstruct_anagrams(a: number): number;
-export function __wbg_set_resultsstruct_anagrams(a: number, b: number): void;
-export function __wbg_get_resultsstruct_partials(a: number): number;
-export function __wbg_set_resultsstruct_partials(a: number, b: number): void;
-export function js_generate(a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number): number;
-export function __wbindgen_malloc(a: number): number;
-export function __wbindgen_realloc(a: number, b: number, c: number): number;
-export function __wbindgen_free(a: number, b: number): void;
+export const __wbg_anagram_free: (a: number, b: number) => void;
+export const __wbg_anagramsolver_free: (a: number, b: number) => void;
+export const __wbg_anagramstream_free: (a: number, b: number) => void;
+export const __wbg_bigrams_free: (a: number, b: number) => void;
+export const __wbg_dictionarysize_free: (a: number, b: number) => void;
+export const __wbg_get_anagram_letters_used: (a: number) => number;
+export const __wbg_get_anagram_per_word_counts: (a: number) => [number, number];
+export const __wbg_get_anagram_score: (a: number) => number;
+export const __wbg_get_anagram_words: (a: number) => [number, number];
+export const __wbg_get_dictionarysize_compressed: (a: number) => number;
+export const __wbg_get_dictionarysize_uncompressed: (a: number) => number;
+export const __wbg_get_dictionarysize_word_list: (a: number) => number;
+export const __wbg_get_query_bigrams: (a: number) => number;
+export const __wbg_get_query_excludes: (a: number) => [number, number];
+export const __wbg_get_query_includes: (a: number) => [number, number];
+export const __wbg_get_query_max_nodes: (a: number) => [number, number];
+export const __wbg_get_query_max_num_words: (a: number) => number;
+export const __wbg_get_query_max_results: (a: number) => number;
+export const __wbg_get_query_min_length: (a: number) => number;
+export const __wbg_get_query_scorer: (a: number) => [number, number];
+export const __wbg_get_query_seed: (a: number) => [number, number];
+export const __wbg_get_query_timeout_ms: (a: number) => [number, number];
+export const __wbg_get_query_top_n: (a: number) => number;
+export const __wbg_get_resultsstruct_anagrams: (a: number) => [number, number];
+export const __wbg_get_resultsstruct_partials: (a: number) => [number, number];
+export const __wbg_get_resultsstruct_truncated: (a: number) => [number, number];
+export const __wbg_query_free: (a: number, b: number) => void;
+export const __wbg_resultsstruct_free: (a: number, b: number) => void;
+export const __wbg_set_anagram_letters_used: (a: number, b: number) => void;
+export const __wbg_set_anagram_per_word_counts: (a: number, b: number, c: number) => void;
+export const __wbg_set_anagram_score: (a: number, b: number) => void;
+export const __wbg_set_anagram_words: (a: number, b: number, c: number) => void;
+export const __wbg_set_dictionarysize_compressed: (a: number, b: number) => void;
+export const __wbg_set_dictionarysize_uncompressed: (a: number, b: number) => void;
+export const __wbg_set_dictionarysize_word_list: (a: number, b: number) => void;
+export const __wbg_set_query_bigrams: (a: number, b: number) => void;
+export const __wbg_set_query_excludes: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_includes: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_max_nodes: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_max_num_words: (a: number, b: number) => void;
+export const __wbg_set_query_max_results: (a: number, b: number) => void;
+export const __wbg_set_query_min_length: (a: number, b: number) => void;
+export const __wbg_set_query_scorer: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_seed: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_timeout_ms: (a: number, b: number, c: number) => void;
+export const __wbg_set_query_top_n: (a: number, b: number) => void;
+export const __wbg_set_resultsstruct_anagrams: (a: number, b: number, c: number) => void;
+export const __wbg_set_resultsstruct_partials: (a: number, b: number, c: number) => void;
+export const __wbg_set_resultsstruct_truncated: (a: number, b: number, c: number) => void;
+export const anagramsolver_add_word: (a: number, b: number, c: number, d: number) => [number, number, number];
+export const anagramsolver_from_index: (a: number, b: number) => [number, number, number];
+export const anagramsolver_from_map: (a: any) => [number, number, number];
+export const anagramsolver_from_tsv: (a: number, b: number) => [number, number, number];
+export const anagramsolver_from_words: (a: number, b: number) => [number, number, number];
+export const anagramsolver_generate: (a: number, b: number) => [number, number, number];
+export const anagramsolver_generate_best: (a: number, b: number, c: number) => [number, number, number];
+export const anagramsolver_new: () => number;
+export const anagramsolver_page: (a: number, b: number, c: number, d: number) => [number, number, number];
+export const anagramsolver_remove_word: (a: number, b: number, c: number) => number;
+export const anagramsolver_resume: (a: number, b: number, c: number, d: number) => [number, number, number];
+export const anagramsolver_sample: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
+export const anagramsolver_stream: (a: number, b: number) => [number, number, number];
+export const anagramsolver_to_index: (a: number) => [number, number];
+export const anagramstream_cancel: (a: number) => void;
+export const anagramstream_next_batch: (a: number, b: number) => [number, number];
+export const anagramstream_partials: (a: number) => [number, number];
+export const anagramstream_resume_token: (a: number) => [number, number];
+export const anagramstream_truncated: (a: number) => [number, number];
+export const bigrams_from_tsv: (a: number, b: number) => [number, number, number];
+export const builtin_dictionary_size: () => number;
+export const js_generate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number) => [number, number, number];
+export const query_new: (a: number, b: number) => number;
+export const wasm_bindgen__convert__closures_____invoke__h05375c079e383286: (a: number, b: number, c: any, d: any) => void;
+export const __wbindgen_malloc: (a: number, b: number) => number;
+export const __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
+export const __wbindgen_free: (a: number, b: number, c: number) => void;
+export const __wbindgen_externrefs: WebAssembly.Table;
+export const __externref_table_dealloc: (a: number) => void;
+export const __externref_table_alloc: () => number;
+export const __externref_drop_slice: (a: number, b: number) => void;
+export const __wbindgen_start: () => void;
@@ -0,0 +1,1381 @@
+use crate::budget::{Budget, CancellationToken, Stopwatch, Truncation};
+use crate::resume::{self, Checkpoint, Stage};
+use crate::sample::{Rng, Sampling};
+use crate::{
+    add_counters, counter_contains, counter_length, is_partial_anagram, normalize_word,
+    subtract_counters, to_counter_indexed, AgarError, Anagram, AnagramCount, Counter, Index, Query,
+    Scorer, WordKey,
+};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rustc_hash::FxHashMap;
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/** A level of the depth-first search: the keys that fit in what is left of the target. */
+struct Frame {
+    keys: Rc<[WordKey]>,
+    position: usize,
+    target_length: usize,
+    // the path is kept in ascending order of key, so each anagram is only found once
+    min_key: WordKey,
+}
+
+/** An anagram ranked by any scorer, for `Anagrams::best_scored`. */
+struct Scored {
+    score: f32,
+    // earlier anagrams rank higher among equal scores
+    order: Reverse<usize>,
+    anagram: Anagram,
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| self.order.cmp(&other.order))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+/** A word that fits in what is left of the target, for `Anagrams::best`. */
+#[derive(Clone, Copy)]
+struct Candidate {
+    count: u32,
+    key: WordKey,
+    // position of the word among the words of its key
+    choice: usize,
+}
+
+/** An anagram found by `Anagrams::best`, ranked by its score. */
+struct Ranked {
+    score: f32,
+    words: Vec<usize>,
+    anagram: Anagram,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.score
+            .total_cmp(&other.score)
+            // break ties in favour of more common words, which have lower ids
+            .then_with(|| other.words.cmp(&self.words))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+// scores are f32s, so a bound worked out in f64 can come out a rounding error below the score it
+// bounds; a little slack only costs a little pruning
+const BOUND_SLACK: f64 = 1e-3;
+
+/** The state of `Anagrams::best`: the `k` best anagrams so far, worst on top. */
+struct Best {
+    k: usize,
+    heap: BinaryHeap<Reverse<Ranked>>,
+    // the counts of the words on the current path
+    counts: Vec<u32>,
+    // the candidates for each target counter, most common first
+    cache: FxHashMap<Counter, Rc<[Candidate]>>,
+}
+
+impl Best {
+    /**
+     * Whether an anagram scoring at most `bound` might make the cut. One tied with the worst so
+     * far might, since ties are broken by its words.
+     */
+    fn admits(&self, bound: f64) -> bool {
+        match self.heap.peek() {
+            Some(Reverse(worst)) if self.heap.len() == self.k => {
+                let worst = worst.score as f64;
+                bound >= worst - BOUND_SLACK - worst.abs() * BOUND_SLACK
+            }
+            _ => true,
+        }
+    }
+
+    /**
+     * Whether adding a word with `count` to the current path, and then between `min_more` and
+     * `max_more` words with counts of at most `most`, might make the cut.
+     */
+    fn admits_with(
+        &mut self,
+        scorer: &dyn Scorer,
+        count: u32,
+        min_more: usize,
+        max_more: usize,
+        most: u32,
+    ) -> bool {
+        self.counts.push(count);
+        let bound = scorer.bound(&self.counts, min_more, max_more, most);
+        self.counts.pop();
+        self.admits(bound.unwrap_or(f64::INFINITY))
+    }
+
+    fn offer(&mut self, words: &[usize], anagram: Anagram) {
+        let ranked = Ranked {
+            score: anagram.score,
+            words: words.to_vec(),
+            anagram,
+        };
+        if self.heap.len() < self.k
+            || self
+                .heap
+                .peek()
+                .is_some_and(|Reverse(worst)| ranked > *worst)
+        {
+            self.heap.push(Reverse(ranked));
+            if self.heap.len() > self.k {
+                self.heap.pop();
+            }
+        }
+    }
+}
+
+/** Memoised counts for `Anagrams::count_anagrams` and `Anagrams::sample`. */
+struct Tally {
+    // how the first count weighs anagrams; the second counts each multiset of keys once
+    sampling: Sampling,
+    memo: FxHashMap<(Counter, usize), (BigUint, BigUint)>,
+}
+
+impl Tally {
+    fn new(sampling: Sampling) -> Self {
+        Tally {
+            sampling,
+            memo: FxHashMap::default(),
+        }
+    }
+}
+
+/**
+ * Iterator over the anagrams of a query. The search runs one step at a time, so results are
+ * available immediately and memory stays bounded by the depth of the search.
+ */
+pub struct Anagrams {
+    search: Arc<Search>,
+    cache: FxHashMap<Counter, Rc<[WordKey]>>,
+    target_counter: Counter,
+    stack: Vec<Frame>,
+    path: Vec<WordKey>,
+    // whether `path` holds a complete anagram whose words are being expanded
+    expanding: bool,
+    // whether the current words were found but not yet returned, when resuming
+    pending: bool,
+    choices: Vec<usize>,
+    words: Vec<usize>,
+    budget: Budget,
+    cancellation: CancellationToken,
+    stopwatch: Stopwatch,
+    nodes: u64,
+    // nodes tried by every search sharing a budget, if `max_nodes` is set
+    shared_nodes: Option<Arc<AtomicU64>>,
+    results: usize,
+    truncated: Option<Truncation>,
+}
+
+/**
+ * What a query searches: the target and the words that fit in it. This is worked out once per
+ * query, and shared by the iterators searching its branches in parallel.
+ */
+pub(crate) struct Search {
+    index: Arc<Index>,
+    includes: Vec<String>,
+    include_counts: Vec<u32>,
+    scorer: Arc<dyn Scorer>,
+    // keys usable in this query, with the words allowed for each
+    key_to_words: FxHashMap<WordKey, Vec<usize>>,
+    partials: Vec<usize>,
+    min_word_length: usize,
+    max_num_words: usize,
+    // the whole target, for starting each branch from
+    initial_counter: Counter,
+    initial_length: usize,
+    // identifies the query and dictionary in resume tokens
+    fingerprint: u64,
+}
+
+impl Anagrams {
+    pub(crate) fn new(index: Arc<Index>, query: &Query) -> Result<Self, AgarError> {
+        // filter out all non-abecedarian characters
+        let mut target = query
+            .seed
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_lowercase();
+
+        // an include of only whitespace has no letters to take, and no word to show
+        let includes = query
+            .includes
+            .iter()
+            .map(|word| normalize_word(word))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        let excludes = query
+            .excludes
+            .iter()
+            .map(|word| normalize_word(word))
+            .collect::<HashSet<_>>();
+
+        let mut fits = true;
+        for included in &includes {
+            if !is_partial_anagram(&target, included)? {
+                fits = false;
+                break;
+            }
+
+            for c in included.chars() {
+                target = target.replacen(&c.to_string(), "", 1);
+            }
+        }
+
+        let target_counter = to_counter_indexed(&target, &index.index_map)?;
+        let mut key_to_words = FxHashMap::default();
+        let mut partials = Vec::new();
+
+        if fits {
+            // restrict the dictionary to the words that fit in the target
+            let mut contained_keys = Vec::new();
+            index
+                .root
+                .retrieve_anagrams(&target_counter, 0, &mut contained_keys);
+
+            for key in contained_keys {
+                if index.key_to_length[key as usize] < query.min_length {
+                    continue;
+                }
+
+                let words = index.key_to_words[key as usize]
+                    .iter()
+                    .copied()
+                    .filter(|&id| {
+                        (id < query.top_n || id >= index.ranked)
+                            && !excludes.contains(index.word(id))
+                    })
+                    .collect::<Vec<_>>();
+
+                if !words.is_empty() {
+                    partials.extend_from_slice(&words);
+                    key_to_words.insert(key, words);
+                }
+            }
+            partials.sort_unstable();
+        }
+
+        let include_counts = includes
+            .iter()
+            .map(|word| index.word_id(word).map_or(0, |id| index.counts[id]))
+            .collect();
+
+        let fingerprint = resume::fingerprint(query, index.generation);
+        let max_num_words = query.max_num_words.saturating_sub(includes.len());
+        let search = Search {
+            index,
+            includes,
+            include_counts,
+            scorer: query.scorer.clone(),
+            key_to_words,
+            partials,
+            min_word_length: query.min_length,
+            max_num_words,
+            initial_counter: target_counter,
+            initial_length: target.len(),
+            fingerprint,
+        };
+
+        let mut anagrams = Anagrams::with_search(Arc::new(search), query);
+        if fits && !target.is_empty() && max_num_words > 0 {
+            let keys = anagrams.candidates();
+            anagrams.stack.push(Frame {
+                keys,
+                position: 0,
+                target_length: target.len(),
+                min_key: 0,
+            });
+        }
+
+        Ok(anagrams)
+    }
+
+    /**
+     * A fresh iterator over an already prepared search, with its own cache and budget but no
+     * branch started yet.
+     */
+    pub(crate) fn with_search(search: Arc<Search>, query: &Query) -> Self {
+        Anagrams {
+            target_counter: search.initial_counter,
+            path: Vec::with_capacity(search.initial_length),
+            search,
+            cache: FxHashMap::default(),
+            stack: Vec::new(),
+            expanding: false,
+            pending: false,
+            choices: Vec::new(),
+            words: Vec::new(),
+            budget: query.budget,
+            cancellation: query.cancellation.clone(),
+            stopwatch: Stopwatch::start(),
+            nodes: 0,
+            shared_nodes: None,
+            results: 0,
+            truncated: None,
+        }
+    }
+
+    /** The prepared search, for starting other iterators over the same query. */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn search(&self) -> Arc<Search> {
+        self.search.clone()
+    }
+
+    /** Every word that fits in the seed, in dictionary order. */
+    pub fn partials(&self) -> Vec<String> {
+        self.search
+            .partials
+            .iter()
+            .map(|&id| self.search.index.word(id).to_string())
+            .collect()
+    }
+
+    /** Why the search stopped early, if it did. */
+    pub fn truncated(&self) -> Option<Truncation> {
+        self.truncated
+    }
+
+    /** A token that stops this search when cancelled. */
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /** Number of candidate words tried so far. */
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    fn exceeded_budget(&self) -> Option<Truncation> {
+        let nodes = match &self.shared_nodes {
+            Some(shared) => shared.fetch_add(1, Ordering::Relaxed) + 1,
+            None => self.nodes,
+        };
+        self.budget
+            .exceeded(&self.cancellation, &self.stopwatch, nodes)
+    }
+
+    /** The keys the search starts from, each the first word of an independent branch. */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn first_keys(&self) -> Vec<WordKey> {
+        self.stack
+            .first()
+            .map_or_else(Vec::new, |frame| frame.keys.to_vec())
+    }
+
+    /**
+     * Share the clock and node count of a search split across several iterators, so their
+     * budget applies to all of them together.
+     */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn share_budget(&mut self, stopwatch: Stopwatch, nodes: Option<Arc<AtomicU64>>) {
+        self.stopwatch = stopwatch;
+        self.shared_nodes = nodes;
+    }
+
+    /** Restart the search on the branch whose first word has `key`, keeping the cache. */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start_branch(&mut self, key: WordKey) {
+        self.target_counter = self.search.initial_counter;
+        self.stack.clear();
+        self.path.clear();
+        self.expanding = false;
+        self.pending = false;
+        self.results = 0;
+        self.truncated = None;
+
+        // other branches may already have used up the budget
+        let nodes = self
+            .shared_nodes
+            .as_ref()
+            .map_or(self.nodes, |shared| shared.load(Ordering::Relaxed));
+        if let Some(reason) = self
+            .budget
+            .exceeded_now(&self.cancellation, &self.stopwatch, nodes)
+        {
+            self.truncated = Some(reason);
+            return;
+        }
+
+        self.stack.push(Frame {
+            keys: Rc::new([key]),
+            position: 0,
+            target_length: self.search.initial_length,
+            min_key: 0,
+        });
+    }
+
+    /**
+     * A token for carrying on from where the search stopped, with `AnagramSolver::resume`, or
+     * `None` once the search has run to the end.
+     */
+    pub fn resume_token(&self) -> Option<String> {
+        if self.stack.is_empty() && !self.expanding {
+            return None;
+        }
+        let (stage, position) = if self.expanding {
+            let stage = if self.pending || self.truncated == Some(Truncation::MaxResults) {
+                Stage::Pending
+            } else {
+                Stage::Returned
+            };
+            (stage, 0)
+        } else {
+            (
+                Stage::Searching,
+                self.stack.last().map_or(0, |frame| frame.position),
+            )
+        };
+        // any word of a key stands for it while searching
+        let words = self
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let choice = if self.expanding { self.choices[i] } else { 0 };
+                self.search.key_to_words[key][choice]
+            })
+            .collect();
+        Some(resume::encode(&Checkpoint {
+            fingerprint: self.search.fingerprint,
+            stage,
+            position,
+            words,
+        }))
+    }
+
+    /** Restore the state of a fresh search to the one saved in `token`. */
+    pub(crate) fn resume(&mut self, token: &str) -> Result<(), AgarError> {
+        let invalid = |reason: &str| AgarError::InvalidResumeToken {
+            reason: reason.to_string(),
+        };
+        let checkpoint = resume::decode(token)?;
+        if checkpoint.fingerprint != self.search.fingerprint {
+            return Err(invalid("it is for a different query or dictionary"));
+        }
+        let expanding = checkpoint.stage != Stage::Searching;
+        if checkpoint.words.len() > self.search.max_num_words
+            || (expanding && checkpoint.words.is_empty())
+        {
+            return Err(invalid("wrong number of words"));
+        }
+        // only a search between paths has a position to resume from
+        if expanding && checkpoint.position != 0 {
+            return Err(invalid("position out of range"));
+        }
+
+        // the keys and choices the search made for each word
+        let mut path = Vec::with_capacity(checkpoint.words.len());
+        let mut choices = Vec::with_capacity(checkpoint.words.len());
+        for &id in &checkpoint.words {
+            let key = Some(id)
+                .filter(|&id| id < self.search.index.num_words())
+                .and_then(|id| {
+                    to_counter_indexed(self.search.index.word(id), &self.search.index.index_map)
+                        .ok()
+                })
+                .and_then(|counter| self.search.index.counter_to_key.get(&counter).copied())
+                .ok_or_else(|| invalid("unknown word"))?;
+            let choice = self
+                .search
+                .key_to_words
+                .get(&key)
+                .and_then(|ids| ids.iter().position(|&other| other == id))
+                .ok_or_else(|| invalid("word not allowed by the query"))?;
+            path.push(key);
+            choices.push(choice);
+        }
+
+        // retrace the steps of `next_path`, which opens a frame after each key unless the key
+        // completes an anagram
+        for (i, &key) in path.iter().enumerate() {
+            let frame = self
+                .stack
+                .last_mut()
+                .ok_or_else(|| invalid("words do not fit"))?;
+            let position = frame
+                .keys
+                .iter()
+                .position(|&other| other == key)
+                .filter(|_| key >= frame.min_key)
+                .ok_or_else(|| invalid("words out of order"))?;
+            frame.position = position + 1;
+            let target_length = frame.target_length - self.search.index.key_to_length[key as usize];
+
+            self.path.push(key);
+            if expanding && i + 1 == path.len() {
+                if target_length != 0 {
+                    return Err(invalid("words do not use every letter"));
+                }
+                break;
+            }
+            if target_length == 0 {
+                return Err(invalid("words out of order"));
+            }
+            subtract_counters(
+                &mut self.target_counter,
+                &self.search.index.key_to_counter[key as usize],
+            );
+            let keys = self.candidates();
+            self.stack.push(Frame {
+                keys,
+                position: 0,
+                target_length,
+                min_key: key,
+            });
+        }
+
+        if expanding {
+            self.expanding = true;
+            self.pending = checkpoint.stage == Stage::Pending;
+            self.choices = choices;
+            self.words = checkpoint.words;
+        } else if let Some(frame) = self.stack.last_mut() {
+            if checkpoint.position > frame.keys.len() {
+                return Err(invalid("position out of range"));
+            }
+            frame.position = checkpoint.position;
+        }
+        Ok(())
+    }
+
+    /** Stop the search for good, recording why. */
+    fn truncate(&mut self, reason: Truncation) {
+        // the rest of the state stays as it was, for `resume_token`
+        self.truncated = Some(reason);
+    }
+
+    /** Keys that fit in the current target counter, in descending order. */
+    fn candidates(&mut self) -> Rc<[WordKey]> {
+        // the last word has to use up every remaining letter, so it can be looked up directly
+        if self.path.len() + 1 == self.search.max_num_words {
+            return match self.search.index.counter_to_key.get(&self.target_counter) {
+                Some(key) if self.search.key_to_words.contains_key(key) => Rc::new([*key]),
+                _ => Rc::new([]),
+            };
+        }
+
+        if let Some(keys) = self.cache.get(&self.target_counter) {
+            return keys.clone();
+        }
+
+        let mut keys = Vec::new();
+        self.search
+            .index
+            .root
+            .retrieve_anagrams(&self.target_counter, 0, &mut keys);
+        keys.retain(|key| self.search.key_to_words.contains_key(key));
+        glidesort::sort(&mut keys);
+        keys.reverse();
+
+        let keys: Rc<[WordKey]> = keys.into();
+        self.cache.insert(self.target_counter, keys.clone());
+        keys
+    }
+
+    /**
+     * The `k` highest-scoring anagrams, best first, found by branch and bound rather than by
+     * enumerating every anagram. Words are tried most common first, and a branch is abandoned
+     * as soon as the scorer's `bound` says even its most common remaining words could not beat
+     * the `k`th best anagram so far. Ties go to the anagram with more common words, so the order
+     * is total. The budget applies as usual, except for `max_results`.
+     */
+    pub(crate) fn best(&mut self, k: usize) -> Vec<Anagram> {
+        // a scorer without a bound would have to be asked for every anagram anyway
+        if !self.search.scorer.has_bound() {
+            return self.best_scored(k);
+        }
+        let Some(root) = self.stack.pop() else {
+            return Vec::new();
+        };
+        let mut best = Best {
+            k,
+            heap: BinaryHeap::with_capacity(k.saturating_add(1).min(1 << 16)),
+            counts: Vec::with_capacity(self.search.max_num_words),
+            cache: FxHashMap::default(),
+        };
+        if k > 0 {
+            self.branch(&mut best, root.target_length, (0, 0));
+        }
+        self.path.clear();
+        self.words.clear();
+
+        // sorting the reversed entries puts the best first
+        best.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked.anagram)
+            .collect()
+    }
+
+    /**
+     * `best` for scorers that give no bound to prune with, so every anagram is scored. Ties go to
+     * the anagram found first.
+     */
+    fn best_scored(&mut self, k: usize) -> Vec<Anagram> {
+        self.budget.max_results = None;
+        let mut heap = BinaryHeap::with_capacity(k.saturating_add(1).min(1 << 16));
+        let mut found = 0usize;
+        while k > 0 && self.advance() {
+            let anagram = self.to_anagram(self.current());
+            heap.push(Reverse(Scored {
+                score: anagram.score,
+                order: Reverse(found),
+                anagram,
+            }));
+            if heap.len() > k {
+                heap.pop();
+            }
+            found += 1;
+        }
+
+        // sorting the reversed entries puts the best first
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(scored)| scored.anagram)
+            .collect()
+    }
+
+    /** The words that fit in the current target counter, most common first. */
+    fn candidates_by_count(&mut self, best: &mut Best) -> Rc<[Candidate]> {
+        // `candidates` looks up the last word directly, so those are not worth caching
+        let last = self.path.len() + 1 == self.search.max_num_words;
+        if !last {
+            if let Some(candidates) = best.cache.get(&self.target_counter) {
+                return candidates.clone();
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for &key in self.candidates().iter() {
+            for (choice, &id) in self.search.key_to_words[&key].iter().enumerate() {
+                candidates.push(Candidate {
+                    count: self.search.index.counts[id],
+                    key,
+                    choice,
+                });
+            }
+        }
+        glidesort::sort_by(&mut candidates, |a, b| b.count.cmp(&a.count));
+
+        let candidates: Rc<[Candidate]> = candidates.into();
+        if !last {
+            best.cache.insert(self.target_counter, candidates.clone());
+        }
+        candidates
+    }
+
+    /**
+     * Try every word that can follow the current path in `best`, returning false once the budget
+     * runs out. Paths take words in ascending order of (key, choice), starting from `min`, so
+     * each anagram is only found once.
+     */
+    fn branch(&mut self, best: &mut Best, target_length: usize, min: (WordKey, usize)) -> bool {
+        let candidates = self.candidates_by_count(best);
+        let eligible = |candidate: &&Candidate| (candidate.key, candidate.choice) >= min;
+        // no word deeper in this branch can be more common than the first eligible one
+        let Some(most) = candidates.iter().find(eligible).map(|c| c.count) else {
+            return true;
+        };
+        let words_left = self.search.max_num_words - self.path.len();
+
+        for candidate in candidates.iter().filter(eligible) {
+            let key_length = self.search.index.key_to_length[candidate.key as usize];
+            let new_target_length = target_length - key_length;
+
+            // ignoring lengths, the bound only gets lower from here since candidates are sorted
+            // by count
+            if !best.admits_with(
+                &*self.search.scorer,
+                candidate.count,
+                0,
+                words_left - 1,
+                most,
+            ) {
+                break;
+            }
+
+            self.nodes += 1;
+            if let Some(reason) = self.exceeded_budget() {
+                self.truncate(reason);
+                return false;
+            }
+
+            let id = self.search.key_to_words[&candidate.key][candidate.choice];
+            if new_target_length == 0 {
+                self.words.push(id);
+                best.offer(&self.words, self.to_anagram(&self.words));
+                self.words.pop();
+                continue;
+            }
+            // as in `next_path`, the remaining words are at least as long as this one
+            if new_target_length < self.search.min_word_length
+                || words_left == 1
+                || new_target_length < key_length
+            {
+                continue;
+            }
+            let max_more = (words_left - 1).min(new_target_length / key_length.max(1));
+            if !best.admits_with(&*self.search.scorer, candidate.count, 1, max_more, most) {
+                continue;
+            }
+
+            self.path.push(candidate.key);
+            self.words.push(id);
+            best.counts.push(candidate.count);
+            subtract_counters(
+                &mut self.target_counter,
+                &self.search.index.key_to_counter[candidate.key as usize],
+            );
+            let finished = self.branch(best, new_target_length, (candidate.key, candidate.choice));
+            add_counters(
+                &mut self.target_counter,
+                &self.search.index.key_to_counter[candidate.key as usize],
+            );
+            best.counts.pop();
+            self.words.pop();
+            self.path.pop();
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+
+    /**
+     * Count the anagrams this search would find, without finding them. Anagrams are multisets of
+     * words, so rather than fixing an order to count them in, this uses the identity between
+     * complete and power sum symmetric polynomials: the multisets of `n` words with letters `c`
+     * number `h(c, n) = 1/n * sum(j = 1..n, sum(words w, h(c - j * w, n - j)))`. That only depends
+     * on what is left of the target and how many words are left, which memoises well.
+     */
+    pub(crate) fn count_anagrams(&mut self) -> AnagramCount {
+        let mut tally = Tally::new(Sampling::Uniform);
+        let mut count = AnagramCount::default();
+        for (anagrams, letter_combinations) in self.count_by_length(&mut tally) {
+            count.anagrams += anagrams;
+            count.letter_combinations += letter_combinations;
+        }
+        count.truncated = self.truncated;
+        count
+    }
+
+    /**
+     * Draw `num_samples` anagrams at random, with replacement, as weighted by `sampling`. Each
+     * term of the sum in `count_anagrams` stands for one anagram with one of its words marked,
+     * so drawing a term and then the rest of the anagram in the same way is uniform (Nijenhuis
+     * and Wilf's method for random partitions). Nothing is drawn if the budget runs out while
+     * counting, since the draws would be biased.
+     */
+    pub(crate) fn sample(
+        &mut self,
+        num_samples: usize,
+        sampling: Sampling,
+        rng: &mut Rng,
+    ) -> Vec<Anagram> {
+        let mut tally = Tally::new(sampling);
+        let totals = self
+            .count_by_length(&mut tally)
+            .into_iter()
+            .map(|(weight, _)| weight)
+            .collect::<Vec<_>>();
+        let total = totals.iter().sum::<BigUint>();
+        if self.truncated.is_some() || total.is_zero() {
+            return Vec::new();
+        }
+
+        let mut samples = Vec::with_capacity(num_samples.min(1 << 16));
+        for _ in 0..num_samples {
+            let mut choice = rng.below(&total);
+            let mut num_words = 1;
+            for weight in &totals {
+                if choice < *weight {
+                    break;
+                }
+                choice -= weight;
+                num_words += 1;
+            }
+            match self.draw(&mut tally, num_words, rng) {
+                Some(words) => samples.push(self.to_anagram(&words)),
+                None => break,
+            }
+        }
+        samples
+    }
+
+    /**
+     * The weighted count and the number of key combinations for each number of words, from one
+     * up, as far as the budget allows.
+     */
+    fn count_by_length(&mut self, tally: &mut Tally) -> Vec<(BigUint, BigUint)> {
+        let Some(root) = self.stack.pop() else {
+            return Vec::new();
+        };
+        let most_words = self
+            .search
+            .max_num_words
+            .min(root.target_length / self.search.min_word_length.max(1));
+
+        let mut counts = Vec::new();
+        for num_words in 1..=most_words {
+            counts.push(self.count_from(tally, num_words));
+            if self.truncated.is_some() {
+                break;
+            }
+        }
+        counts
+    }
+
+    /**
+     * Draw the word ids of an anagram of `num_words` words of the current target, as weighted
+     * by `tally`. Every count needed has already been memoised by `count_by_length`.
+     */
+    fn draw(&mut self, tally: &mut Tally, num_words: usize, rng: &mut Rng) -> Option<Vec<usize>> {
+        let initial_counter = self.target_counter;
+        let mut words = Vec::with_capacity(num_words);
+        let mut num_words = num_words;
+
+        while num_words > 0 {
+            // the sum of the terms, before dividing by the number of words
+            let total = self.count_from(tally, num_words).0 * num_words;
+            let mut choice = rng.below(&total);
+            let mut picked = None;
+
+            'keys: for &key in self.candidates().iter() {
+                let key_counter = self.search.index.key_to_counter[key as usize];
+                let mut times = 0;
+                while times < num_words && counter_contains(&self.target_counter, &key_counter) {
+                    subtract_counters(&mut self.target_counter, &key_counter);
+                    times += 1;
+
+                    let (rest, _) = self.count_from(tally, num_words - times);
+                    let weight = self.weigh(rest, key, times, tally.sampling);
+                    if choice < weight {
+                        picked = Some((key, times));
+                        break 'keys;
+                    }
+                    choice -= weight;
+                }
+                for _ in 0..times {
+                    add_counters(&mut self.target_counter, &key_counter);
+                }
+            }
+
+            // only if the counts were cut short
+            let Some((key, times)) = picked else {
+                self.target_counter = initial_counter;
+                return None;
+            };
+            let id = self.pick_word(key, times, tally.sampling, rng);
+            words.extend(std::iter::repeat_n(id, times));
+            num_words -= times;
+        }
+
+        self.target_counter = initial_counter;
+        Some(words)
+    }
+
+    /** The key of the word that uses up exactly the current target counter, if any. */
+    fn last_key(&self) -> Option<WordKey> {
+        // as in `candidates`, the last word can be looked up directly
+        self.search
+            .index
+            .counter_to_key
+            .get(&self.target_counter)
+            .copied()
+            .filter(|key| self.search.key_to_words.contains_key(key))
+    }
+
+    /** `count` completions following `times` copies of a word of `key`, weighted by `sampling`. */
+    fn weigh(&self, count: BigUint, key: WordKey, times: usize, sampling: Sampling) -> BigUint {
+        let words = &self.search.key_to_words[&key];
+        match sampling {
+            Sampling::Uniform => count * words.len(),
+            Sampling::Frequency => {
+                count
+                    * words
+                        .iter()
+                        .map(|&id| self.word_weight(id).pow(times as u32))
+                        .sum::<BigUint>()
+            }
+        }
+    }
+
+    /** The weight of a word under `Sampling::Frequency`. Words without a count still count. */
+    fn word_weight(&self, id: usize) -> BigUint {
+        BigUint::from(self.search.index.counts[id]) + 1u32
+    }
+
+    /** One of the words of `key`, to be taken `times` times, as weighted by `sampling`. */
+    fn pick_word(&self, key: WordKey, times: usize, sampling: Sampling, rng: &mut Rng) -> usize {
+        let words = &self.search.key_to_words[&key];
+        let weights = match sampling {
+            Sampling::Uniform => vec![BigUint::one(); words.len()],
+            Sampling::Frequency => words
+                .iter()
+                .map(|&id| self.word_weight(id).pow(times as u32))
+                .collect(),
+        };
+
+        let mut choice = rng.below(&weights.iter().sum());
+        for (&id, weight) in words.iter().zip(&weights) {
+            if choice < *weight {
+                return id;
+            }
+            choice -= weight;
+        }
+        unreachable!("the choice is below the total weight")
+    }
+
+    /**
+     * The number of multisets of exactly `num_words` words that use up the current target
+     * counter, weighted as `tally` says, and the number of multisets of keys that do. Stops
+     * early, undercounting, if the budget runs out.
+     */
+    fn count_from(&mut self, tally: &mut Tally, num_words: usize) -> (BigUint, BigUint) {
+        let target_length = counter_length(&self.target_counter);
+        if num_words == 0 || target_length == 0 {
+            let empty = if num_words == 0 && target_length == 0 {
+                BigUint::one()
+            } else {
+                BigUint::zero()
+            };
+            return (empty.clone(), empty);
+        }
+        if target_length < num_words * self.search.min_word_length.max(1) {
+            return (BigUint::zero(), BigUint::zero());
+        }
+        if num_words == 1 {
+            return match self.last_key() {
+                Some(key) => (
+                    self.weigh(BigUint::one(), key, 1, tally.sampling),
+                    BigUint::one(),
+                ),
+                None => (BigUint::zero(), BigUint::zero()),
+            };
+        }
+        if let Some(counts) = tally.memo.get(&(self.target_counter, num_words)) {
+            return counts.clone();
+        }
+
+        let mut weighted = BigUint::zero();
+        let mut key_combinations = BigUint::zero();
+        for &key in self.candidates().iter() {
+            let key_counter = self.search.index.key_to_counter[key as usize];
+
+            // `times` copies of the key, for the power sum of degree `times`
+            let mut times = 0;
+            while times < num_words && counter_contains(&self.target_counter, &key_counter) {
+                subtract_counters(&mut self.target_counter, &key_counter);
+                times += 1;
+
+                self.nodes += 1;
+                if let Some(reason) = self.exceeded_budget() {
+                    self.truncate(reason);
+                    break;
+                }
+                let (rest, rest_combinations) = self.count_from(tally, num_words - times);
+                weighted += self.weigh(rest, key, times, tally.sampling);
+                key_combinations += rest_combinations;
+                if self.truncated.is_some() {
+                    break;
+                }
+            }
+            for _ in 0..times {
+                add_counters(&mut self.target_counter, &key_counter);
+            }
+            if self.truncated.is_some() {
+                break;
+            }
+        }
+        // the sums are exact multiples of `num_words`, unless the search was cut short
+        weighted /= num_words;
+        key_combinations /= num_words;
+
+        if self.truncated.is_none() {
+            tally.memo.insert(
+                (self.target_counter, num_words),
+                (weighted.clone(), key_combinations.clone()),
+            );
+        }
+        (weighted, key_combinations)
+    }
+
+    /** Advance the search to the next complete path of keys. */
+    fn next_path(&mut self) -> bool {
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                return false;
+            };
+
+            if frame.position == frame.keys.len() {
+                self.stack.pop();
+                if let Some(key) = self.path.pop() {
+                    add_counters(
+                        &mut self.target_counter,
+                        &self.search.index.key_to_counter[key as usize],
+                    );
+                }
+                continue;
+            }
+
+            let key = frame.keys[frame.position];
+            frame.position += 1;
+
+            // keys are sorted in descending order, so we can stop if the key is too small
+            if key < frame.min_key {
+                frame.position = frame.keys.len();
+                continue;
+            }
+
+            let key_length = self.search.index.key_to_length[key as usize];
+            let new_target_length = frame.target_length - key_length;
+
+            self.nodes += 1;
+            if let Some(reason) = self.exceeded_budget() {
+                // leave the key to be tried again on resuming
+                if let Some(frame) = self.stack.last_mut() {
+                    frame.position -= 1;
+                }
+                self.truncate(reason);
+                return false;
+            }
+
+            if new_target_length == 0 {
+                self.path.push(key);
+                return true;
+            } else if new_target_length < self.search.min_word_length
+                || self.path.len() == self.search.max_num_words - 1
+            {
+                continue;
+            } else if new_target_length < key_length {
+                // keys are numbered by length and the path is kept in ascending order, so the
+                // remaining words are at least as long as this one
+                continue;
+            }
+
+            self.path.push(key);
+            subtract_counters(
+                &mut self.target_counter,
+                &self.search.index.key_to_counter[key as usize],
+            );
+            let keys = self.candidates();
+
+            // the first candidate is the longest, so give up if even the longest words can't
+            // cover what is left of the target
+            let words_left = self.search.max_num_words - self.path.len();
+            let longest = keys
+                .first()
+                .map_or(0, |&k| self.search.index.key_to_length[k as usize]);
+            if longest * words_left < new_target_length {
+                self.path.pop();
+                add_counters(
+                    &mut self.target_counter,
+                    &self.search.index.key_to_counter[key as usize],
+                );
+                continue;
+            }
+
+            self.stack.push(Frame {
+                keys,
+                position: 0,
+                target_length: new_target_length,
+                min_key: key,
+            });
+        }
+    }
+
+    /** Advance to the next combination of words for the current path, if any. */
+    fn next_choice(&mut self) -> bool {
+        for i in (0..self.choices.len()).rev() {
+            self.choices[i] += 1;
+            if self.choices[i] < self.search.key_to_words[&self.path[i]].len() {
+                // a key used several times takes its words in ascending order, so the same
+                // words are not found again in a different order
+                for j in i + 1..self.choices.len() {
+                    self.choices[j] = if self.path[j] == self.path[j - 1] {
+                        self.choices[j - 1]
+                    } else {
+                        0
+                    };
+                }
+                return true;
+            }
+            self.choices[i] = 0;
+        }
+        false
+    }
+
+    /** Move on to the next anagram, whose word ids are then available from `current`. */
+    pub(crate) fn advance(&mut self) -> bool {
+        if self.truncated.is_some() {
+            return false;
+        }
+
+        // expand each key to all possible words, taking the cartesian product
+        if !(std::mem::take(&mut self.pending) || self.expanding && self.next_choice()) {
+            if self.expanding {
+                self.path.pop();
+            }
+            self.expanding = self.next_path();
+            if !self.expanding {
+                return false;
+            }
+            self.choices.clear();
+            self.choices.resize(self.path.len(), 0);
+        }
+
+        self.words.clear();
+        for (key, &choice) in self.path.iter().zip(&self.choices) {
+            self.words.push(self.search.key_to_words[key][choice]);
+        }
+
+        // only report truncation once another anagram has actually been found
+        if self.budget.max_results == Some(self.results) {
+            self.truncate(Truncation::MaxResults);
+            return false;
+        }
+        self.results += 1;
+        true
+    }
+
+    /** The word ids of the current anagram, excluding the included words. */
+    pub(crate) fn current(&self) -> &[usize] {
+        &self.words
+    }
+
+    /** The full anagram for the given word ids, with the included words first. */
+    pub(crate) fn to_anagram(&self, words: &[usize]) -> Anagram {
+        Anagram::new(
+            &self.search.includes,
+            &self.search.include_counts,
+            words
+                .iter()
+                .map(|&id| (self.search.index.word(id), self.search.index.counts[id])),
+            &*self.search.scorer,
+        )
+    }
+}
+
+impl Iterator for Anagrams {
+    type Item = Anagram;
+
+    fn next(&mut self) -> Option<Anagram> {
+        if !self.advance() {
+            return None;
+        }
+        Some(self.to_anagram(self.current()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_solver as solver;
+    use crate::{parse_scorer, Anagram, Query};
+    use num_bigint::BigUint;
+    use std::collections::HashSet;
+
+    fn queries() -> Vec<Query> {
+        let mut queries = Vec::new();
+        for seed in ["stop a rat", "sprat", "tops art", "a a a", "post it", "zzz"] {
+            for (min_length, max_num_words) in [(1, 5), (2, 3), (3, 2), (1, 1)] {
+                let mut query = Query::new(seed);
+                query.min_length = min_length;
+                query.max_num_words = max_num_words;
+                queries.push(query);
+            }
+        }
+        let mut query = Query::new("stop a rat");
+        query.min_length = 1;
+        query.includes = vec!["Star".to_string()];
+        query.excludes = ["pot", "TOP"].map(String::from).into();
+        queries.push(query.clone());
+        query.top_n = 20;
+        queries.push(query);
+        queries
+    }
+
+    #[test]
+    fn counts_match_enumeration() {
+        let solver = solver();
+        for query in queries() {
+            let solution = solver.solve(&query).unwrap();
+            let count = solver.count(&query).unwrap();
+            assert_eq!(count.truncated, None);
+            assert_eq!(
+                count.anagrams,
+                BigUint::from(solution.anagrams.len()),
+                "{:?}",
+                query.seed
+            );
+
+            // the same anagrams, up to words with the same letters
+            let combinations = solution
+                .anagrams
+                .iter()
+                .map(|anagram| {
+                    let mut letters = anagram
+                        .words
+                        .iter()
+                        .map(|word| {
+                            let mut letters = word.chars().collect::<Vec<_>>();
+                            letters.sort_unstable();
+                            letters
+                        })
+                        .collect::<Vec<_>>();
+                    letters.sort_unstable();
+                    letters
+                })
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                count.letter_combinations,
+                BigUint::from(combinations.len()),
+                "{:?}",
+                query.seed
+            );
+        }
+    }
+
+    #[test]
+    fn blank_includes_are_ignored() {
+        let solver = solver();
+        let mut query = Query::new("stop a rat");
+        query.max_num_words = 2;
+        let expected = solver.solve(&query).unwrap().anagrams;
+        assert!(!expected.is_empty());
+
+        query.includes = vec![" ".to_string(), String::new()];
+        let solution = solver.solve(&query).unwrap();
+        assert_eq!(solution.anagrams, expected);
+        assert_eq!(
+            solver.count(&query).unwrap().anagrams,
+            BigUint::from(expected.len())
+        );
+    }
+
+    #[test]
+    fn counts_find_some_anagrams() {
+        let mut query = Query::new("stop a rat");
+        query.min_length = 1;
+        let count = solver().count(&query).unwrap();
+        assert!(count.anagrams > BigUint::from(100u32));
+        assert!(count.letter_combinations < count.anagrams);
+    }
+
+    fn scores(anagrams: &[Anagram]) -> Vec<f32> {
+        anagrams.iter().map(|anagram| anagram.score).collect()
+    }
+
+    #[test]
+    fn best_matches_the_top_of_solve() {
+        let solver = solver();
+        for query in queries() {
+            let all = solver.solve(&query).unwrap().anagrams;
+            for k in [0, 1, 2, 5, 17, all.len(), all.len() + 3] {
+                let best = solver.solve_best(&query, k).unwrap();
+                assert_eq!(best.truncated, None);
+                assert_eq!(
+                    scores(&best.anagrams),
+                    scores(&all[..k.min(all.len())]),
+                    "{:?} with k = {}",
+                    query.seed,
+                    k
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn best_breaks_ties_consistently() {
+        let solver = solver();
+        let mut query = Query::new("stop a rat");
+        query.min_length = 1;
+        let all = solver.solve(&query).unwrap().anagrams;
+        // different words can have the same mean count, so there are ties
+        assert!(all.windows(2).any(|pair| pair[0].score == pair[1].score));
+
+        let best = solver.solve_best(&query, all.len()).unwrap().anagrams;
+        let mut sorted = best.iter().map(Anagram::to_string).collect::<Vec<_>>();
+        let mut expected = all.iter().map(Anagram::to_string).collect::<Vec<_>>();
+        sorted.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+        for k in 1..20 {
+            assert_eq!(solver.solve_best(&query, k).unwrap().anagrams, best[..k]);
+        }
+    }
+
+    #[test]
+    fn best_matches_solve_for_every_scorer() {
+        let solver = solver();
+        for spec in ["geometric", "min", "2*mean+100*fewer-words", "longest-word"] {
+            for mut query in queries() {
+                query.scorer = parse_scorer(spec).unwrap();
+                let all = solver.solve(&query).unwrap().anagrams;
+                let best = solver.solve_best(&query, 10).unwrap().anagrams;
+                assert_eq!(
+                    scores(&best),
+                    scores(&all[..10.min(all.len())]),
+                    "{:?} by {}",
+                    query.seed,
+                    spec
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pages_follow_best() {
+        let solver = solver();
+        for query in queries() {
+            let best = solver.solve_best(&query, usize::MAX).unwrap().anagrams;
+            for limit in [1, 3, 10] {
+                let mut paged = Vec::new();
+                for offset in (0..best.len() + limit).step_by(limit) {
+                    let page = solver.solve_page(&query, offset, limit).unwrap().anagrams;
+                    assert_eq!(
+                        page,
+                        best[offset.min(best.len())..(offset + limit).min(best.len())]
+                    );
+                    paged.extend(page);
+                }
+                assert_eq!(paged, best, "{:?} in pages of {}", query.seed, limit);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/** Limits on how much work a single search may do. `None` means unlimited. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub max_results: Option<usize>,
+    // number of candidate words tried by the depth-first search
+    pub max_nodes: Option<u64>,
+    // wall-clock time, measured from when the search starts
+    pub deadline: Option<Duration>,
+}
+
+// reading the clock is comparatively slow, so only check the deadline every this many nodes
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+impl Budget {
+    /** Why a search that has visited `nodes` nodes should stop now, if it should. */
+    pub(crate) fn exceeded(
+        &self,
+        cancellation: &CancellationToken,
+        stopwatch: &Stopwatch,
+        nodes: u64,
+    ) -> Option<Truncation> {
+        self.check(
+            cancellation,
+            stopwatch,
+            nodes,
+            nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL),
+        )
+    }
+
+    /** Like `exceeded`, but always reading the clock. */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn exceeded_now(
+        &self,
+        cancellation: &CancellationToken,
+        stopwatch: &Stopwatch,
+        nodes: u64,
+    ) -> Option<Truncation> {
+        self.check(cancellation, stopwatch, nodes, true)
+    }
+
+    fn check(
+        &self,
+        cancellation: &CancellationToken,
+        stopwatch: &Stopwatch,
+        nodes: u64,
+        read_clock: bool,
+    ) -> Option<Truncation> {
+        if cancellation.is_cancelled() {
+            return Some(Truncation::Cancelled);
+        }
+        if self.max_nodes.is_some_and(|max| nodes > max) {
+            return Some(Truncation::MaxNodes);
+        }
+        if let Some(deadline) = self.deadline {
+            if read_clock && stopwatch.elapsed() >= deadline {
+                return Some(Truncation::Deadline);
+            }
+        }
+        None
+    }
+}
+
+/** Why a search stopped before enumerating every anagram. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Truncation {
+    MaxResults,
+    MaxNodes,
+    Deadline,
+    Cancelled,
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Truncation::MaxResults => "max_results",
+            Truncation::MaxNodes => "max_nodes",
+            Truncation::Deadline => "deadline",
+            Truncation::Cancelled => "cancelled",
+        };
+        f.write_str(reason)
+    }
+}
+
+/** Shared flag for stopping a search from another thread (or from a JS callback). */
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/** `Instant` panics on wasm32-unknown-unknown, so use the JS clock there. */
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start: f64,
+}
+
+impl Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            start: js_sys::Date::now(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.start).max(0.0) / 1000.0)
+    }
+}
@@ -0,0 +1,219 @@
+// `agar-man batch`: solve a list of seeds against one solver, writing a JSON record per seed.
+
+use crate::{apply_query_option, parse_number, split_args, usage, Args, CliError, QUERY_OPTIONS};
+use agar_man::{AgarError, AnagramSolver, Query, Truncation};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Lines,
+    Csv,
+}
+
+/** The outcome of solving one seed. */
+#[derive(Serialize)]
+struct Record {
+    // line of the input the seed came from
+    line: usize,
+    seed: String,
+    // as returned by `counter_solve`: anagrams sorted by score, then every word that fits
+    anagrams: Vec<String>,
+    partials: Vec<String>,
+    // before `--limit` is applied
+    num_anagrams: usize,
+    num_partials: usize,
+    elapsed_ms: f64,
+    truncated: Option<Truncation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Record {
+    fn failed(line: usize, seed: String, error: CliError) -> Self {
+        Record {
+            line,
+            seed,
+            anagrams: Vec::new(),
+            partials: Vec::new(),
+            num_anagrams: 0,
+            num_partials: 0,
+            elapsed_ms: 0.0,
+            truncated: None,
+            // the usage text would be noise in every record
+            error: Some(match error {
+                CliError::Usage(message) => message,
+                error => error.to_string(),
+            }),
+        }
+    }
+}
+
+fn solve(solver: &AnagramSolver, line: usize, query: &Query, limit: Option<usize>) -> Record {
+    let start = Instant::now();
+    let solution = match solver.solve(query) {
+        Ok(solution) => solution,
+        Err(error) => return Record::failed(line, query.seed.clone(), error.into()),
+    };
+    let elapsed = start.elapsed();
+
+    let num_anagrams = solution.anagrams.len();
+    Record {
+        line,
+        seed: query.seed.clone(),
+        anagrams: solution
+            .anagrams
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|anagram| anagram.to_string())
+            .collect(),
+        num_anagrams,
+        num_partials: solution.partials.len(),
+        partials: solution.partials,
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        truncated: solution.truncated,
+        error: None,
+    }
+}
+
+/** Columns are named after the options, e.g. `max_words` for `--max-words`. */
+fn column_option(header: &str) -> String {
+    format!("--{}", header.replace('_', "-"))
+}
+
+/** The query for a CSV row: the defaults, overridden by any non-empty cells. */
+fn row_query(
+    defaults: &Query,
+    headers: &csv::StringRecord,
+    row: &csv::StringRecord,
+) -> Result<Query, CliError> {
+    let mut query = defaults.clone();
+    for (header, value) in headers.iter().zip(row.iter()) {
+        if header == "seed" {
+            query.seed = value.to_string();
+        } else if !value.is_empty() {
+            apply_query_option(&mut query, &column_option(header), value)?;
+        }
+    }
+    Ok(query)
+}
+
+pub(crate) fn run(args: &[String]) -> Result<(), CliError> {
+    let Args {
+        positional: paths,
+        options,
+    } = split_args(args)?;
+    if paths.len() > 1 {
+        return Err(usage("batch reads a single file"));
+    }
+    let path = paths.first().copied().filter(|&path| path != "-");
+
+    let mut defaults = Query::new("");
+    let mut limit = None;
+    let mut input = match path {
+        Some(path) if path.ends_with(".csv") => Input::Csv,
+        _ => Input::Lines,
+    };
+    for (option, value) in options {
+        if apply_query_option(&mut defaults, option, value)? {
+            continue;
+        }
+        match option {
+            "--limit" => limit = Some(parse_number(option, value)?),
+            "--input" => {
+                input = match value {
+                    "lines" => Input::Lines,
+                    "csv" => Input::Csv,
+                    _ => return Err(usage(format!("unknown input format {:?}", value))),
+                }
+            }
+            _ => return Err(usage(format!("unknown option {}", option))),
+        }
+    }
+
+    let reader: Box<dyn Read> = match path {
+        Some(path) => Box::new(File::open(path).map_err(|error| AgarError::Io {
+            path: path.to_string(),
+            message: error.to_string(),
+        })?),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    // preprocess the dictionary once for the whole batch
+    let start = Instant::now();
+    let solver = AnagramSolver::new();
+    let mut out = BufWriter::new(io::stdout().lock());
+    let (mut num_seeds, mut num_failed) = (0, 0);
+
+    let mut write = |record: Record| -> Result<(), CliError> {
+        num_seeds += 1;
+        num_failed += record.error.is_some() as usize;
+        serde_json::to_writer(&mut out, &record).map_err(io::Error::from)?;
+        writeln!(out)?;
+        // flush every record, so long batches can be followed as they run
+        out.flush()?;
+        Ok(())
+    };
+
+    match input {
+        Input::Lines => {
+            for (i, line) in BufReader::new(reader).lines().enumerate() {
+                let line = line?;
+                let seed = line.trim();
+                if seed.is_empty() {
+                    continue;
+                }
+                let query = Query {
+                    seed: seed.to_string(),
+                    ..defaults.clone()
+                };
+                write(solve(&solver, i + 1, &query, limit))?;
+            }
+        }
+        Input::Csv => {
+            let mut rows = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .flexible(true)
+                .from_reader(reader);
+            let headers = rows.headers().map_err(io::Error::from)?.clone();
+            let Some(seed_column) = headers.iter().position(|header| header == "seed") else {
+                return Err(usage("the CSV needs a `seed` column"));
+            };
+            if let Some(header) = headers.iter().find(|&header| {
+                header != "seed" && !QUERY_OPTIONS.contains(&column_option(header).as_str())
+            }) {
+                return Err(usage(format!("unknown column {:?}", header)));
+            }
+
+            let mut row = csv::StringRecord::new();
+            loop {
+                let line = rows.position().line() as usize;
+                let record = match rows.read_record(&mut row) {
+                    Ok(false) => break,
+                    Ok(true) => match row_query(&defaults, &headers, &row) {
+                        Ok(query) => solve(&solver, line, &query, limit),
+                        Err(error) => {
+                            let seed = row.get(seed_column).unwrap_or_default().to_string();
+                            Record::failed(line, seed, error)
+                        }
+                    },
+                    // e.g. invalid UTF-8; the other rows can still be read
+                    Err(error) => {
+                        Record::failed(line, String::new(), io::Error::from(error).into())
+                    }
+                };
+                write(record)?;
+            }
+        }
+    }
+
+    eprintln!(
+        "{} seeds in {:?}, {} failed",
+        num_seeds,
+        start.elapsed(),
+        num_failed
+    );
+    Ok(())
+}
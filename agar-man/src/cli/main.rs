@@ -0,0 +1,435 @@
+use agar_man::{
+    normalize_word, parse_scorer, AgarError, AnagramSolver, BigramScorer, Bigrams, Differential,
+    Engine, Query, Solution,
+};
+use std::env;
+use std::fmt;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod batch;
+mod repl;
+#[cfg(not(target_arch = "wasm32"))]
+mod serve;
+
+const USAGE: &str = "usage: agar-man solve <seed>... [options]
+       agar-man count <seed>... [query options]
+       agar-man batch [<file>] [options]
+       agar-man repl [<seed>...] [query options]
+       agar-man serve [options]
+       agar-man prep <1grams|2grams>
+       agar-man version
+
+query options:
+  --min-length <n>    shortest word to use (default 3)
+  --max-words <n>     most words in an anagram (default 5)
+  --include <words>   words every anagram must contain, comma-separated or repeated
+  --exclude <words>   words never to use, comma-separated or repeated
+  --top-n <n>         only use the n most common dictionary words
+  --scorer <scorer>   mean, geometric, min, fewer-words or longest-word, or a weighted blend
+                      such as mean+100*fewer-words (default mean)
+  --bigrams <file>    rank by bigram likelihood instead, from `first<TAB>second<TAB>count`
+                      lines, putting the words of each anagram in their likeliest order
+  --max-results <n>   stop searching after n anagrams
+  --timeout <ms>      stop searching after this many milliseconds
+
+solve options:
+  --limit <n>         only print the n best anagrams
+  --best <n>          only search for the n best anagrams, which is faster than --limit
+                      (counter engine only)
+  --offset <n>        with --best, skip the n best anagrams first, for the next page
+  --format <format>   text, tsv or json (default text)
+  --engine <engine>   counter, parallel, trie or differential (default counter)
+
+batch options:
+  --limit <n>         only keep the n best anagrams of each seed
+  --input <format>    lines (one seed per line) or csv (default: from the file extension)
+
+serve options:
+  --host <host>       address to listen on (default 127.0.0.1)
+  --port <port>       port to listen on (default 8080)
+  --threads <n>       requests answered at once (default: one per core)
+  --max-words <n>     largest max_num_words a request may ask for (default 6)
+  --max-results <n>   most anagrams a request may return (default 10000)
+  --timeout <ms>      longest a request may search for (default 2000)
+
+A batch reads seeds from the file, or from stdin if there is none, and writes one JSON record per
+seed. A CSV needs a `seed` column, and may set query options per row with columns named after
+them, such as `max_words` or `include`. The options on the command line are the defaults.
+
+The server answers POST /solve with a JSON query such as {\"seed\": \"kevin hu\", \"includes\": [\"hi\"]},
+POST /verify with {\"seed\": ..., \"words\": [...]}, and GET /words?letters=...
+
+prep rebuilds the word lists from the Google Books n-grams, from the crate root and with the `prep`
+feature: 1grams turns ./data/1grams/*.gz into src/dictionary_counts.txt, and 2grams turns
+./data/2grams/*.gz into data/bigram_counts.txt, for --bigrams.";
+
+/** Problems with the command line, or with running it. */
+enum CliError {
+    Usage(String),
+    Agar(AgarError),
+    Io(io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Agar(error) => error.fmt(f),
+            CliError::Io(error) => error.fmt(f),
+        }
+    }
+}
+
+impl From<AgarError> for CliError {
+    fn from(error: AgarError) -> Self {
+        CliError::Agar(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Tsv,
+    Json,
+}
+
+/** Everything `solve` needs besides the dictionary. */
+struct SolveArgs {
+    query: Query,
+    limit: Option<usize>,
+    // only search for this many anagrams, after skipping `offset`
+    best: Option<usize>,
+    offset: usize,
+    format: Format,
+    engine: String,
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| usage(format!("{} expects a number, got {:?}", option, value)))
+}
+
+/** Comma-separated words, as in the web form. */
+fn parse_words(value: &str) -> impl Iterator<Item = String> + '_ {
+    // normalised as the solver sees them, so `drop` in the repl can find them again
+    value
+        .split(',')
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+}
+
+// the options that shape a `Query`, shared by every command
+const QUERY_OPTIONS: [&str; 9] = [
+    "--min-length",
+    "--max-words",
+    "--include",
+    "--exclude",
+    "--top-n",
+    "--scorer",
+    "--bigrams",
+    "--max-results",
+    "--timeout",
+];
+
+/**
+ * Apply one of the options that shape a `Query`. Returns false if `option` is not one of them,
+ * so callers can handle their own options.
+ */
+fn apply_query_option(query: &mut Query, option: &str, value: &str) -> Result<bool, CliError> {
+    match option {
+        "--min-length" => query.min_length = parse_number(option, value)?,
+        "--max-words" => query.max_num_words = parse_number(option, value)?,
+        "--include" => query.includes.extend(parse_words(value)),
+        "--exclude" => query.excludes.extend(parse_words(value)),
+        "--top-n" => query.top_n = parse_number(option, value)?,
+        "--scorer" => query.scorer = parse_scorer(value)?,
+        "--bigrams" => query.scorer = Arc::new(BigramScorer(Arc::new(Bigrams::from_path(value)?))),
+        "--max-results" => query.budget.max_results = Some(parse_number(option, value)?),
+        "--timeout" => {
+            query.budget.deadline = Some(Duration::from_millis(parse_number(option, value)?))
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/** A command's arguments, split into positional ones and `--option value` pairs. */
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+}
+
+fn split_args(args: &[String]) -> Result<Args<'_>, CliError> {
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg.as_str());
+            continue;
+        }
+
+        // accept both `--option value` and `--option=value`
+        match arg.split_once('=') {
+            Some(option) => options.push(option),
+            None => match args.next() {
+                Some(value) => options.push((arg.as_str(), value.as_str())),
+                None => return Err(usage(format!("{} expects a value", arg))),
+            },
+        }
+    }
+
+    Ok(Args {
+        positional,
+        options,
+    })
+}
+
+fn parse_solve(args: &[String]) -> Result<SolveArgs, CliError> {
+    let Args {
+        positional: seed,
+        options,
+    } = split_args(args)?;
+    if seed.is_empty() {
+        return Err(usage("missing seed"));
+    }
+
+    let mut solve = SolveArgs {
+        // the seed may be quoted or not
+        query: Query::new(&seed.join(" ")),
+        limit: None,
+        best: None,
+        offset: 0,
+        format: Format::Text,
+        engine: "counter".to_string(),
+    };
+
+    for (option, value) in options {
+        if apply_query_option(&mut solve.query, option, value)? {
+            continue;
+        }
+        match option {
+            "--limit" => solve.limit = Some(parse_number(option, value)?),
+            "--best" => solve.best = Some(parse_number(option, value)?),
+            "--offset" => solve.offset = parse_number(option, value)?,
+            "--format" => {
+                solve.format = match value {
+                    "text" => Format::Text,
+                    "tsv" => Format::Tsv,
+                    "json" => Format::Json,
+                    _ => return Err(usage(format!("unknown format {:?}", value))),
+                }
+            }
+            "--engine" => solve.engine = value.to_string(),
+            _ => return Err(usage(format!("unknown option {}", option))),
+        }
+    }
+
+    if solve.offset > 0 && solve.best.is_none() {
+        return Err(usage("--offset needs --best"));
+    }
+    Ok(solve)
+}
+
+#[cfg(feature = "trie")]
+fn trie_engine() -> Result<Box<dyn Engine>, CliError> {
+    let dictionary = agar_man::WordList::builtin();
+    Ok(Box::new(agar_man::TrieEngine::new(&dictionary)?))
+}
+
+#[cfg(not(feature = "trie"))]
+fn trie_engine() -> Result<Box<dyn Engine>, CliError> {
+    Err(usage("the trie engine needs the `trie` feature"))
+}
+
+/** The counter engine checked against the trie engine. */
+fn differential() -> Result<Differential, CliError> {
+    Ok(Differential::new(
+        Box::new(AnagramSolver::new()),
+        trie_engine()?,
+    ))
+}
+
+fn engine(name: &str) -> Result<Box<dyn Engine>, CliError> {
+    match name {
+        "counter" => Ok(Box::new(AnagramSolver::new())),
+        #[cfg(not(target_arch = "wasm32"))]
+        "parallel" => Ok(Box::new(agar_man::ParallelSolver(AnagramSolver::new()))),
+        "trie" => trie_engine(),
+        "differential" => Ok(Box::new(differential()?)),
+        _ => Err(usage(format!("unknown engine {:?}", name))),
+    }
+}
+
+fn print_solution(
+    out: &mut impl Write,
+    solution: &Solution,
+    limit: Option<usize>,
+    format: Format,
+) -> io::Result<()> {
+    let count = limit.unwrap_or(usize::MAX).min(solution.anagrams.len());
+    let anagrams = &solution.anagrams[..count];
+
+    match format {
+        Format::Text => {
+            for anagram in anagrams {
+                writeln!(out, "{:>8.1}  {}", anagram.score, anagram)?;
+            }
+        }
+        Format::Tsv => {
+            for anagram in anagrams {
+                writeln!(out, "{}\t{}", anagram, anagram.score)?;
+            }
+        }
+        Format::Json => {
+            let solution = Solution {
+                anagrams: anagrams.to_vec(),
+                partials: solution.partials.clone(),
+                truncated: solution.truncated,
+            };
+            serde_json::to_writer(&mut *out, &solution)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()
+}
+
+fn solve(args: &[String]) -> Result<(), CliError> {
+    let args = parse_solve(args)?;
+    let solution = match args.best {
+        Some(k) if args.engine == "counter" => {
+            AnagramSolver::new().solve_page(&args.query, args.offset, k)?
+        }
+        Some(_) => return Err(usage("--best only works with the counter engine")),
+        // report how the engines compared even when they agree
+        None if args.engine == "differential" => {
+            let comparison = differential()?.compare(&args.query)?;
+            if !comparison.agrees() {
+                return Err(AgarError::EnginesDisagree {
+                    seed: args.query.seed.clone(),
+                    report: comparison.to_string(),
+                }
+                .into());
+            }
+            eprintln!("{}", comparison);
+            comparison.reference.solution
+        }
+        None => engine(&args.engine)?.solve(&args.query)?,
+    };
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    print_solution(&mut out, &solution, args.limit, args.format)?;
+
+    if let Some(reason) = solution.truncated {
+        eprintln!("search stopped early: {}", reason);
+    }
+    Ok(())
+}
+
+/** Print how many anagrams a seed has, without finding them. */
+fn count(args: &[String]) -> Result<(), CliError> {
+    let Args {
+        positional: seed,
+        options,
+    } = split_args(args)?;
+    if seed.is_empty() {
+        return Err(usage("missing seed"));
+    }
+
+    let mut query = Query::new(&seed.join(" "));
+    for (option, value) in options {
+        if !apply_query_option(&mut query, option, value)? {
+            return Err(usage(format!("unknown option {}", option)));
+        }
+    }
+
+    let count = AnagramSolver::new().count(&query)?;
+    let mut out = io::stdout().lock();
+    writeln!(out, "{} anagrams", count.anagrams)?;
+    writeln!(
+        out,
+        "{} counting words with the same letters once",
+        count.letter_combinations
+    )?;
+
+    if let Some(reason) = count.truncated {
+        eprintln!("count stopped early: {}; these are lower bounds", reason);
+    }
+    Ok(())
+}
+
+/** Run the offline steps that turn downloaded n-grams into word lists. */
+#[cfg(feature = "prep")]
+fn prep(args: &[String]) -> Result<(), CliError> {
+    use agar_man::dictionary_prep;
+
+    match args {
+        [step] if step == "1grams" => {
+            dictionary_prep::aggregate_1grams()?;
+            dictionary_prep::filter_1grams()?;
+            dictionary_prep::assign_counts()?;
+        }
+        [step] if step == "2grams" => dictionary_prep::aggregate_2grams()?,
+        _ => return Err(usage("expected prep 1grams or prep 2grams")),
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "prep"))]
+fn prep(_args: &[String]) -> Result<(), CliError> {
+    Err(usage("prep needs the `prep` feature"))
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    match args.first().map(String::as_str) {
+        Some("solve") => solve(&args[1..]),
+        Some("count") => count(&args[1..]),
+        Some("batch") => batch::run(&args[1..]),
+        Some("repl") => repl::run(&args[1..]),
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("serve") => serve::run(&args[1..]),
+        Some("prep") => prep(&args[1..]),
+        Some("version" | "--version" | "-V") => {
+            println!("agar-man {}", env!("CARGO_PKG_VERSION"));
+            println!("dictionary: {}", agar_man::builtin_dictionary_size());
+            Ok(())
+        }
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(usage(format!("unknown command {:?}", command))),
+        None => Err(usage("missing command")),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        // e.g. piping into `head`
+        Err(CliError::Io(error)) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("agar-man: {}", error);
+            ExitCode::from(match error {
+                CliError::Usage(_) => 2,
+                _ => 1,
+            })
+        }
+    }
+}
@@ -0,0 +1,215 @@
+// `agar-man repl`: refine one query interactively, reusing the solver and the last results.
+
+use crate::{
+    apply_query_option, parse_number, parse_words, print_solution, split_args, usage, Args,
+    CliError, Format,
+};
+use agar_man::{AnagramSolver, Query, Solution};
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+const HELP: &str = "commands:
+  seed <letters>        start over with a new seed
+  include <words>       words every anagram must contain, which must fit in the seed
+  exclude <words>       words never to use
+  drop <words>          stop including or excluding words
+  reset                 drop every included and excluded word
+  min-length <n>        shortest word to use
+  max-words <n>         most words in an anagram
+  top-n <n>             only use the n most common dictionary words
+  max-results <n>       stop searching after n anagrams
+  timeout <ms>          stop searching after this many milliseconds
+  top <n>               how many anagrams to show
+  remaining             the letters left after the included words
+  show                  the current query and results
+  help                  this message
+  quit                  leave
+
+Separate words with commas, so that phrases such as `new york` stay whole.";
+
+// keep the prompt responsive for seeds with millions of anagrams
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Repl {
+    solver: AnagramSolver,
+    query: Query,
+    top: usize,
+    // the results for `query`, until it changes
+    solution: Option<(Solution, Duration)>,
+}
+
+/** The letters of the seed that the included words leave over, in alphabetical order. */
+fn remaining(query: &Query) -> Result<String, String> {
+    let mut letters = query
+        .seed
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    for word in &query.includes {
+        for c in word.to_lowercase().chars().filter(|&c| c != ' ') {
+            match letters.iter().position(|&letter| letter == c) {
+                Some(i) => {
+                    letters.remove(i);
+                }
+                None => return Err(format!("{:?} does not fit in what is left", word)),
+            }
+        }
+    }
+    letters.sort_unstable();
+    Ok(letters.into_iter().collect())
+}
+
+impl Repl {
+    fn show(&mut self, out: &mut impl Write) -> Result<(), CliError> {
+        if self.query.seed.is_empty() {
+            writeln!(out, "no seed yet; try `seed <letters>`")?;
+            return Ok(());
+        }
+        if let Err(message) = remaining(&self.query) {
+            writeln!(out, "{}", message)?;
+            return Ok(());
+        }
+
+        if self.solution.is_none() {
+            let start = Instant::now();
+            let solution = self.solver.solve(&self.query)?;
+            self.solution = Some((solution, start.elapsed()));
+        }
+        let (solution, elapsed) = self.solution.as_ref().expect("solved above");
+
+        print_solution(out, solution, Some(self.top), Format::Text)?;
+        write!(
+            out,
+            "{} anagrams of {:?} in {:?}",
+            solution.anagrams.len(),
+            self.query.seed,
+            elapsed
+        )?;
+        if let Some(reason) = solution.truncated {
+            write!(out, " (stopped early: {})", reason)?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn describe(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut excludes = self.query.excludes.iter().collect::<Vec<_>>();
+        excludes.sort_unstable();
+        writeln!(
+            out,
+            "seed {:?}, include {:?}, exclude {:?}, min-length {}, max-words {}",
+            self.query.seed,
+            self.query.includes,
+            excludes,
+            self.query.min_length,
+            self.query.max_num_words
+        )
+    }
+
+    /** Like `command`, but only failing if the session cannot go on. */
+    fn run_command(&mut self, line: &str, out: &mut impl Write) -> Result<bool, CliError> {
+        match self.command(line, out) {
+            // mistakes should not end the session
+            Err(CliError::Usage(message)) => writeln!(out, "{}", message)?,
+            Err(CliError::Agar(error)) => writeln!(out, "{}", error)?,
+            result => return result,
+        }
+        Ok(true)
+    }
+
+    /** Run one command, returning false once the user wants to leave. */
+    fn command(&mut self, line: &str, out: &mut impl Write) -> Result<bool, CliError> {
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        match command {
+            "" => return Ok(true),
+            "quit" | "exit" => return Ok(false),
+            "help" => writeln!(out, "{}", HELP)?,
+            "show" => {
+                self.describe(out)?;
+                self.show(out)?;
+            }
+            "remaining" => match remaining(&self.query) {
+                Ok(letters) => writeln!(out, "{} ({} letters)", letters, letters.len())?,
+                Err(message) => writeln!(out, "{}", message)?,
+            },
+            "top" => {
+                self.top = parse_number("top", argument)?;
+                self.show(out)?;
+            }
+            _ => {
+                match command {
+                    "seed" => self.query.seed = argument.to_string(),
+                    "drop" => {
+                        for word in parse_words(argument) {
+                            self.query.includes.retain(|included| *included != word);
+                            self.query.excludes.remove(&word);
+                        }
+                    }
+                    "reset" => {
+                        self.query.includes.clear();
+                        self.query.excludes.clear();
+                    }
+                    _ => {
+                        let mut query = self.query.clone();
+                        let option = format!("--{}", command);
+                        if !apply_query_option(&mut query, &option, argument)? {
+                            return Err(usage(format!("unknown command {:?}", command)));
+                        }
+                        // an include that does not fit would leave no anagrams at all
+                        if command == "include" && !query.seed.is_empty() {
+                            remaining(&query).map_err(usage)?;
+                        }
+                        self.query = query;
+                    }
+                }
+                self.solution = None;
+                self.show(out)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+pub(crate) fn run(args: &[String]) -> Result<(), CliError> {
+    let Args {
+        positional: seed,
+        options,
+    } = split_args(args)?;
+
+    let mut query = Query::new(&seed.join(" "));
+    query.budget.deadline = Some(DEFAULT_TIMEOUT);
+    for (option, value) in options {
+        if !apply_query_option(&mut query, option, value)? {
+            return Err(usage(format!("unknown option {}", option)));
+        }
+    }
+
+    let mut repl = Repl {
+        solver: AnagramSolver::new(),
+        query,
+        top: 10,
+        solution: None,
+    };
+
+    let mut out = io::stdout().lock();
+    writeln!(out, "type `help` for the list of commands")?;
+    if !repl.query.seed.is_empty() {
+        repl.run_command("show", &mut out)?;
+    }
+
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        let Some(line) = lines.next() else {
+            writeln!(out)?;
+            return Ok(());
+        };
+        if !repl.run_command(line?.trim(), &mut out)? {
+            return Ok(());
+        }
+    }
+}
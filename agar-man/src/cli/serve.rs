@@ -0,0 +1,291 @@
+// `agar-man serve`: answer queries over HTTP, for tools that cannot link the library.
+
+use crate::{parse_number, split_args, usage, Args, CliError};
+use agar_man::{parse_scorer, AgarError, AnagramSolver, Query};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/** What a single request may ask of the server, so one heavy query cannot hold up the rest. */
+#[derive(Clone, Copy)]
+struct Limits {
+    max_num_words: usize,
+    timeout: Duration,
+    max_results: usize,
+    max_body: usize,
+}
+
+/** The body of `POST /solve`, mirroring `Query`. Omitted fields take the `Query` defaults. */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SolveRequest {
+    seed: String,
+    min_length: Option<usize>,
+    max_num_words: Option<usize>,
+    #[serde(default)]
+    includes: Vec<String>,
+    #[serde(default)]
+    excludes: Vec<String>,
+    top_n: Option<usize>,
+    scorer: Option<String>,
+    max_results: Option<usize>,
+    max_nodes: Option<u64>,
+    timeout_ms: Option<u64>,
+}
+
+/** The body of `POST /verify`. */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VerifyRequest {
+    seed: String,
+    words: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct WordsResponse {
+    words: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+fn http_error(status: u16, message: impl Into<String>) -> HttpError {
+    HttpError {
+        status,
+        message: message.into(),
+    }
+}
+
+impl From<AgarError> for HttpError {
+    fn from(error: AgarError) -> Self {
+        http_error(400, error.to_string())
+    }
+}
+
+impl SolveRequest {
+    fn into_query(self, limits: &Limits) -> Result<Query, HttpError> {
+        let mut query = Query::new(&self.seed);
+        query.min_length = self.min_length.unwrap_or(query.min_length);
+        query.max_num_words = self.max_num_words.unwrap_or(query.max_num_words);
+        query.includes = self.includes;
+        query.excludes = self.excludes.into_iter().collect();
+        query.top_n = self.top_n.unwrap_or(query.top_n);
+        if let Some(scorer) = &self.scorer {
+            query.scorer = parse_scorer(scorer)?;
+        }
+
+        if query.max_num_words > limits.max_num_words {
+            return Err(http_error(
+                400,
+                format!("max_num_words may be at most {}", limits.max_num_words),
+            ));
+        }
+        // budgets can only be tightened; the response says if they cut the search short
+        query.budget.max_results = Some(
+            self.max_results
+                .map_or(limits.max_results, |max| max.min(limits.max_results)),
+        );
+        query.budget.max_nodes = self.max_nodes;
+        query.budget.deadline = Some(self.timeout_ms.map_or(limits.timeout, |timeout| {
+            Duration::from_millis(timeout).min(limits.timeout)
+        }));
+        Ok(query)
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    request: &mut Request,
+    limits: &Limits,
+) -> Result<T, HttpError> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(limits.max_body as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|error| http_error(400, error.to_string()))?;
+    if body.len() > limits.max_body {
+        return Err(http_error(
+            413,
+            format!("request bodies may be at most {} bytes", limits.max_body),
+        ));
+    }
+    serde_json::from_slice(&body).map_err(|error| http_error(400, error.to_string()))
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, HttpError> {
+    serde_json::to_string(value).map_err(|error| http_error(500, error.to_string()))
+}
+
+/** Decode a `application/x-www-form-urlencoded` component, e.g. `new+york` or `new%20york`. */
+fn decode_component(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/** `GET /words?letters=...`: every word that can be made from the letters, most common first. */
+fn words(solver: &AnagramSolver, parameters: &str) -> Result<String, HttpError> {
+    let mut query = Query::new("");
+    // only the partials are needed, so skip the search itself
+    query.max_num_words = 0;
+    let mut letters = None;
+
+    for parameter in parameters.split('&').filter(|p| !p.is_empty()) {
+        let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+        let value = decode_component(value);
+        let number = || {
+            value
+                .parse()
+                .map_err(|_| http_error(400, format!("{} expects a number", name)))
+        };
+        match name {
+            "letters" => letters = Some(value.clone()),
+            "min_length" => query.min_length = number()?,
+            "top_n" => query.top_n = number()?,
+            _ => return Err(http_error(400, format!("unknown parameter {:?}", name))),
+        }
+    }
+
+    query.seed = letters.ok_or_else(|| http_error(400, "missing letters"))?;
+    let solution = solver.solve(&query)?;
+    to_json(&WordsResponse {
+        words: solution.partials,
+    })
+}
+
+fn handle(
+    solver: &AnagramSolver,
+    limits: &Limits,
+    request: &mut Request,
+) -> Result<String, HttpError> {
+    let url = request.url().to_string();
+    let (path, parameters) = url.split_once('?').unwrap_or((&url, ""));
+
+    match (request.method(), path) {
+        (Method::Post, "/solve") => {
+            let query = read_json::<SolveRequest>(request, limits)?.into_query(limits)?;
+            to_json(&solver.solve(&query)?)
+        }
+        (Method::Post, "/verify") => {
+            let body = read_json::<VerifyRequest>(request, limits)?;
+            to_json(&solver.verify(&body.seed, &body.words)?)
+        }
+        (Method::Get, "/words") => words(solver, parameters),
+        (_, "/solve" | "/verify" | "/words") => Err(http_error(405, "method not allowed")),
+        _ => Err(http_error(404, "not found")),
+    }
+}
+
+fn respond(solver: &AnagramSolver, limits: &Limits, mut request: Request) {
+    let start = Instant::now();
+    let (status, body) = match handle(solver, limits, &mut request) {
+        Ok(body) => (200, body),
+        Err(error) => (
+            error.status,
+            serde_json::to_string(&ErrorResponse {
+                error: error.message,
+            })
+            .unwrap_or_default(),
+        ),
+    };
+
+    eprintln!(
+        "{} {} {} {:?}",
+        request.method(),
+        request.url(),
+        status,
+        start.elapsed()
+    );
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("header is valid ASCII");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    // the client may have gone away, which only concerns that client
+    let _ = request.respond(response);
+}
+
+pub(crate) fn run(args: &[String]) -> Result<(), CliError> {
+    let Args {
+        positional,
+        options,
+    } = split_args(args)?;
+    if let Some(arg) = positional.first() {
+        return Err(usage(format!("unexpected argument {:?}", arg)));
+    }
+
+    let mut host = "127.0.0.1".to_string();
+    let mut port: u16 = 8080;
+    let mut threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let mut limits = Limits {
+        max_num_words: 6,
+        timeout: Duration::from_secs(2),
+        max_results: 10_000,
+        max_body: 64 * 1024,
+    };
+    for (option, value) in options {
+        match option {
+            "--host" => host = value.to_string(),
+            "--port" => port = parse_number(option, value)?,
+            "--threads" => threads = parse_number::<usize>(option, value)?.max(1),
+            "--max-words" => limits.max_num_words = parse_number(option, value)?,
+            "--max-results" => limits.max_results = parse_number(option, value)?,
+            "--timeout" => limits.timeout = Duration::from_millis(parse_number(option, value)?),
+            _ => return Err(usage(format!("unknown option {}", option))),
+        }
+    }
+
+    let solver = AnagramSolver::new();
+    let server = Server::http((host.as_str(), port))
+        .map_err(|error| CliError::Io(io::Error::other(error)))?;
+    let server = Arc::new(server);
+    eprintln!(
+        "listening on http://{}:{} with {} threads",
+        host, port, threads
+    );
+
+    // each worker answers one request at a time, so a slow query only ties up its own thread
+    let workers = (0..threads)
+        .map(|_| {
+            let server = server.clone();
+            let solver = solver.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(&solver, &limits, request);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use crate::layout::Layout;
+use crate::{builtin_index, layout, normalize_word, AgarError};
+use rustc_hash::FxHashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/** A source of words and their frequency counts for building an `AnagramSolver`. */
+pub trait Dictionary {
+    /** Every word with its count. Words are ranked by descending count for `Query::top_n`. */
+    fn entries(&self) -> Box<dyn Iterator<Item = (&str, u32)> + '_>;
+}
+
+/** An in-memory list of words and counts, as loaded from a file, a buffer or JS. */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordList {
+    entries: Vec<(String, u32)>,
+}
+
+impl WordList {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /** The dictionary compiled into the crate, read back from its embedded index. */
+    pub fn builtin() -> Self {
+        let bytes = builtin_index();
+        let layout = Layout::decode(&bytes).expect("built-in index is valid");
+        WordList {
+            entries: (0..layout.len())
+                .map(|id| (layout.word(id).to_string(), layout.counts[id]))
+                .collect(),
+        }
+    }
+
+    /** Parse `word\tcount` lines; a word may be a phrase, and a line without a count gets 0. */
+    pub fn from_tsv(text: &str) -> Result<Self, AgarError> {
+        let mut list = WordList::new();
+        for (line_number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid_line = || AgarError::InvalidDictionaryLine {
+                line_number: line_number + 1,
+                line: line.to_string(),
+            };
+            let (word, count) = layout::parse_entry(line).ok_or_else(invalid_line)?;
+            list.push(word, count);
+        }
+        Ok(list)
+    }
+
+    /** Parse a UTF-8 buffer of `word\tcount` lines. */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AgarError> {
+        let text = std::str::from_utf8(bytes).map_err(|error| AgarError::InvalidUtf8 {
+            position: error.valid_up_to(),
+        })?;
+        WordList::from_tsv(text)
+    }
+
+    /** Read a file of `word\tcount` lines. */
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, AgarError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|error| AgarError::Io {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        WordList::from_bytes(&bytes)
+    }
+
+    /** Add a word, lowercased, after the existing entries. */
+    pub fn push(&mut self, word: &str, count: u32) {
+        self.entries.push((word.to_lowercase(), count));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Dictionary for WordList {
+    fn entries(&self) -> Box<dyn Iterator<Item = (&str, u32)> + '_> {
+        Box::new(
+            self.entries
+                .iter()
+                .map(|(word, count)| (word.as_str(), *count)),
+        )
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, u32)> for WordList {
+    fn from_iter<I: IntoIterator<Item = (S, u32)>>(iter: I) -> Self {
+        let mut list = WordList::new();
+        for (word, count) in iter {
+            list.push(word.as_ref(), count);
+        }
+        list
+    }
+}
+
+/**
+ * How often pairs of words occur one after the other, for `BigramScorer`. Counts are on the same
+ * log scale as dictionary counts, as written by `dictionary_prep::aggregate_2grams`.
+ */
+#[derive(Clone, Default)]
+pub struct Bigrams {
+    // words get ids so that pairs can be looked up without building strings
+    ids: FxHashMap<Box<str>, u32>,
+    counts: FxHashMap<(u32, u32), u32>,
+}
+
+impl Bigrams {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /** Parse `first\tsecond\tcount` lines, where either word may be a phrase. */
+    pub fn from_tsv(text: &str) -> Result<Self, AgarError> {
+        let mut bigrams = Bigrams::new();
+        for (line_number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid_line = || AgarError::InvalidBigramLine {
+                line_number: line_number + 1,
+                line: line.to_string(),
+            };
+            let mut fields = line.split('\t');
+            let (Some(first), Some(second), Some(count), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid_line());
+            };
+            let count = count.trim().parse().map_err(|_| invalid_line())?;
+            bigrams.push(first, second, count);
+        }
+        Ok(bigrams)
+    }
+
+    /** Parse a UTF-8 buffer of `first\tsecond\tcount` lines. */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AgarError> {
+        let text = std::str::from_utf8(bytes).map_err(|error| AgarError::InvalidUtf8 {
+            position: error.valid_up_to(),
+        })?;
+        Bigrams::from_tsv(text)
+    }
+
+    /** Read a file of `first\tsecond\tcount` lines. */
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, AgarError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|error| AgarError::Io {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        Bigrams::from_bytes(&bytes)
+    }
+
+    /** Set the count of a pair, replacing any earlier one. */
+    pub fn push(&mut self, first: &str, second: &str, count: u32) {
+        let first = self.intern(first);
+        let second = self.intern(second);
+        self.counts.insert((first, second), count);
+    }
+
+    fn intern(&mut self, word: &str) -> u32 {
+        let next = self.ids.len() as u32;
+        *self.ids.entry(normalize_word(word).into()).or_insert(next)
+    }
+
+    /** The id of a word, for `pair_count`, if it occurs in any pair. */
+    pub(crate) fn word_id(&self, word: &str) -> Option<u32> {
+        self.ids.get(word).copied()
+    }
+
+    /** The count of a pair of words by id, if the pair was seen. */
+    pub(crate) fn pair_count(&self, first: Option<u32>, second: Option<u32>) -> Option<u32> {
+        self.counts.get(&(first?, second?)).copied()
+    }
+
+    /** The count of `first` followed by `second`, if the pair was seen. */
+    pub fn count(&self, first: &str, second: &str) -> Option<u32> {
+        self.pair_count(self.word_id(first), self.word_id(second))
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+// the table runs to millions of pairs, which are no use in a debug dump
+impl fmt::Debug for Bigrams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bigrams")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,153 +1,222 @@
-extern crate js_sys;
-extern crate wasm_bindgen;
-use flate2::read::GzDecoder;
-use itertools::Itertools;
-use js_sys::Array;
-use rustc_hash::{FxHashMap, FxHasher};
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::hash::BuildHasherDefault;
-use std::io::{BufRead, BufReader, Write};
-use std::time::Instant;
-use std::{cmp, fs, str};
-use wasm_bindgen::prelude::*;
-
-fn aggregate_1grams() {
-    // let mut lines = Vec::new();
-
-    // list files in ./1grams
-    let paths = fs::read_dir("./data/1grams").unwrap();
+// Offline steps that turn the Google Books 1-grams into `dictionary_counts.txt`. Run them in
+// order from the crate root, after downloading the 1-gram files into ./data/1grams, or run
+// `agar-man prep 1grams`. The 2-grams are optional: download them into ./data/2grams and run
+// `aggregate_2grams`, or `agar-man prep 2grams`, for a `Bigrams` table.
 
-    let mut total_counts = Vec::new();
+use flate2::read::GzDecoder;
+use rustc_hash::FxHashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+const ONE_GRAMS: &str = "./data/1grams";
+const TWO_GRAMS: &str = "./data/2grams";
+const BIGRAM_COUNTS: &str = "./data/bigram_counts.txt";
+const TOTAL_COUNTS: &str = "./data/total_counts.txt";
+const FILTERED_COUNTS: &str = "./data/filtered_counts.txt";
+const WORD_LIST: &str = "./src/dictionary.txt";
+const DICTIONARY_COUNTS: &str = "./src/dictionary_counts.txt";
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected line {:?}", line),
+    )
+}
 
-    for path in paths {
-        // ignore if path is not .gz
-        let path = path.unwrap();
-        if path.path().extension().unwrap_or_default() != "gz" {
-            continue;
-        }
+fn parse_count(count: Option<&str>, line: &str) -> io::Result<u64> {
+    count
+        .and_then(|count| count.parse::<u64>().ok())
+        .ok_or_else(|| invalid_line(line))
+}
 
-        println!("Processing {:?}", path);
+/** Sum the counts of an n-gram over the years, given the fields after the n-gram itself. */
+fn sum_years<'a>(years: impl Iterator<Item = &'a str>, line: &str) -> io::Result<u64> {
+    let mut total = 0;
+    for year in years {
+        // format is (year, count, volumes)
+        let mut year_split = year.split(',');
+        year_split.next();
+        total += parse_count(year_split.next(), line)?;
+    }
+    Ok(total)
+}
 
-        // open file
-        let file = File::open(path.path()).unwrap();
-        let reader = BufReader::new(GzDecoder::new(file));
+// words seen less often than this get a count of 0, the same as words never seen at all
+const MIN_FREQUENCY: u64 = 40;
 
-        // read lines
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let mut split = line.split("\t");
-            let word = split.next().unwrap().to_string();
+/** A frequency on the log scale of dictionary counts, where 100 is a factor of two. */
+fn log_count(frequency: u64) -> u32 {
+    ((frequency as f64 / MIN_FREQUENCY as f64).log2() * 100.0) as u32
+}
 
-            let mut total = 0;
+/** The gzipped files in a directory of n-grams. */
+fn gz_files(directory: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for path in fs::read_dir(directory)? {
+        let path = path?.path();
+        if path.extension().unwrap_or_default() == "gz" {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
 
-            for count in split {
-                // format is (year, count, volumes)
-                let mut count_split = count.split(',');
+/** Sum the yearly counts of every word in the gzipped 1-gram files into `total_counts.txt`. */
+pub fn aggregate_1grams() -> io::Result<()> {
+    let mut total_counts = Vec::new();
 
-                let year = count_split.next().unwrap();
-                let count = count_split.next().unwrap();
+    for path in gz_files(ONE_GRAMS)? {
+        println!("Processing {:?}", path);
 
-                // println!("{} {} {}", word, year, count);
+        let reader = BufReader::new(GzDecoder::new(File::open(&path)?));
 
-                total += count.parse::<u64>().unwrap();
-            }
+        for line in reader.lines() {
+            let line = line?;
+            let mut split = line.split('\t');
+            let word = split.next().unwrap_or_default().to_string();
+            let total = sum_years(split, &line)?;
 
             total_counts.push((word, total));
         }
-
-        // println!("Total counts: {:?}", total_counts);
     }
-    let mut out = File::create("./data/total_counts.txt").unwrap();
 
-    // sort by word
     glidesort::sort_by(&mut total_counts, |a, b| a.1.cmp(&b.1));
 
-    // write total counts to file
+    let mut out = BufWriter::new(File::create(TOTAL_COUNTS)?);
     for (word, count) in total_counts {
-        out.write_all(format!("{}\t{}\n", word, count).as_bytes())
-            .unwrap();
+        writeln!(out, "{}\t{}", word, count)?;
     }
+    out.flush()
 }
 
-fn filter_1grams() {
-    // let mut lines = Vec::new();
+/** Merge the counts of alphabetic words case-insensitively into `filtered_counts.txt`. */
+pub fn filter_1grams() -> io::Result<()> {
+    let reader = BufReader::new(File::open(TOTAL_COUNTS)?);
 
-    // read frequencies
-    let file = File::open("./data/total_counts.txt").unwrap();
-    let reader = BufReader::new(file);
-
-    let mut counts = FxHashMap::default();
+    let mut counts: FxHashMap<String, u64> = FxHashMap::default();
 
     for line in reader.lines() {
-        let line = line.unwrap();
-        let mut split = line.split("\t");
-        let word = split.next().unwrap().to_string();
+        let line = line?;
+        let mut split = line.split('\t');
+        let word = split.next().unwrap_or_default();
 
         // only accept if is all alphabetical
         if !word.chars().all(|c| c.is_ascii_alphabetic()) {
             continue;
         }
 
-        let count = split.next().unwrap().parse::<u64>().unwrap();
-        let lowercase = word.to_lowercase();
-
-        // counts.insert(word.to_lowercase(), count);
-
-        if counts.contains_key(&lowercase) {
-            let old_count = counts.get(&lowercase).unwrap();
-            counts.insert(lowercase, old_count + count);
-        } else {
-            counts.insert(lowercase, count);
-        }
+        let count = parse_count(split.next(), &line)?;
+        *counts.entry(word.to_lowercase()).or_default() += count;
     }
 
     let mut v = Vec::from_iter(counts);
     glidesort::sort_by(&mut v, |a, b| b.1.cmp(&a.1));
 
-    let mut filtered = File::create("./data/filtered_counts.txt").unwrap();
-
+    let mut filtered = BufWriter::new(File::create(FILTERED_COUNTS)?);
     for (word, count) in v {
-        filtered
-            .write_all(format!("{}\t{}\n", word, count).as_bytes())
-            .unwrap();
+        writeln!(filtered, "{}\t{}", word, count)?;
     }
+    filtered.flush()
 }
 
-fn assign_counts() {
-    let dictionary = include_str!("dictionary.txt");
-
+/** Score every word of `dictionary.txt` by its log frequency, writing `dictionary_counts.txt`. */
+pub fn assign_counts() -> io::Result<()> {
     let mut counts = FxHashMap::default();
-    let counts_file = File::open("./data/filtered_counts.txt").unwrap();
-    let counts_reader = BufReader::new(counts_file);
+    let counts_reader = BufReader::new(File::open(FILTERED_COUNTS)?);
 
     for line in counts_reader.lines() {
-        let line = line.unwrap();
-        let mut split = line.split("\t");
-        let word = split.next().unwrap().to_string();
-        let count = split.next().unwrap().parse::<u64>().unwrap();
+        let line = line?;
+        let mut split = line.split('\t');
+        let word = split.next().unwrap_or_default().to_string();
+        let count = parse_count(split.next(), &line)?;
 
         counts.insert(word, count);
     }
 
     let mut dictionary_counts = Vec::new();
 
-    for line in dictionary.lines() {
-        // println!("{}", line);
+    for line in fs::read_to_string(WORD_LIST)?.lines() {
         let line = line.to_lowercase();
 
-        let freq = *counts.get(&line).unwrap_or(&40) as f64 / 40.0;
+        let frequency = *counts.get(&line).unwrap_or(&MIN_FREQUENCY);
 
-        dictionary_counts.push((line, (freq.log2() * 100.0) as u32));
+        dictionary_counts.push((line, log_count(frequency)));
     }
 
-    glidesort::sort_by(&mut dictionary_counts, |a, b| {
-        b.1.partial_cmp(&a.1).unwrap()
-    });
-    let mut out = File::create("./src/dictionary_counts.txt").unwrap();
+    glidesort::sort_by(&mut dictionary_counts, |a, b| b.1.cmp(&a.1));
 
+    let mut out = BufWriter::new(File::create(DICTIONARY_COUNTS)?);
     for (word, count) in dictionary_counts {
-        out.write_all(format!("{}\t{}\n", word, count).as_bytes())
-            .unwrap();
+        writeln!(out, "{}\t{}", word, count)?;
+    }
+    out.flush()
+}
+
+/**
+ * Sum the yearly counts of every pair of dictionary words in the gzipped 2-gram files into
+ * `bigram_counts.txt`, as `first\tsecond\tcount` lines for `Bigrams`. Pairs are merged
+ * case-insensitively, and those seen less often than any word with a count are left out.
+ */
+pub fn aggregate_2grams() -> io::Result<()> {
+    // only pairs of dictionary words are kept, which is what makes the table small enough
+    let words = fs::read_to_string(WORD_LIST)?
+        .lines()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    let ids = words
+        .iter()
+        .enumerate()
+        .map(|(id, word)| (word.as_str(), id))
+        .collect::<FxHashMap<_, _>>();
+
+    let mut pair_counts: FxHashMap<(usize, usize), u64> = FxHashMap::default();
+
+    for path in gz_files(TWO_GRAMS)? {
+        println!("Processing {:?}", path);
+
+        let reader = BufReader::new(GzDecoder::new(File::open(&path)?));
+
+        for line in reader.lines() {
+            let line = line?;
+            let mut split = line.split('\t');
+            let bigram = split.next().unwrap_or_default();
+
+            // tagged tokens such as `tree_NOUN` fail the alphabetic check, like in the 1-grams
+            let Some((first, second)) = bigram.split_once(' ') else {
+                continue;
+            };
+            if ![first, second]
+                .iter()
+                .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+            {
+                continue;
+            }
+            let first = ids.get(first.to_lowercase().as_str());
+            let second = ids.get(second.to_lowercase().as_str());
+            let (Some(&first), Some(&second)) = (first, second) else {
+                continue;
+            };
+
+            *pair_counts.entry((first, second)).or_default() += sum_years(split, &line)?;
+        }
+    }
+
+    let mut v = pair_counts
+        .into_iter()
+        .filter(|&(_, count)| count > MIN_FREQUENCY)
+        .collect::<Vec<_>>();
+    glidesort::sort_by(&mut v, |a, b| b.1.cmp(&a.1));
+
+    let mut out = BufWriter::new(File::create(BIGRAM_COUNTS)?);
+    for ((first, second), count) in v {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            words[first],
+            words[second],
+            log_count(count)
+        )?;
     }
+    out.flush()
 }
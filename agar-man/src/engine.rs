@@ -0,0 +1,304 @@
+use crate::budget::Stopwatch;
+use crate::{AgarError, Anagram, AnagramSolver, Query, Solution};
+use std::fmt;
+use std::time::Duration;
+
+/**
+ * A way of answering anagram queries. Engines share `Query` and `Solution`, so they can be
+ * swapped for one another and checked against each other.
+ */
+pub trait Engine {
+    /** Short name used when reporting on the engine. */
+    fn name(&self) -> &'static str;
+
+    /** Find the anagrams of a query within its budget, sorted by score. */
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError>;
+}
+
+impl Engine for AnagramSolver {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        AnagramSolver::solve(self, query)
+    }
+}
+
+/** `AnagramSolver::solve_parallel` as an `Engine`. */
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ParallelSolver(pub AnagramSolver);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Engine for ParallelSolver {
+    fn name(&self) -> &'static str {
+        "parallel"
+    }
+
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        self.0.solve_parallel(query)
+    }
+}
+
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        (**self).solve(query)
+    }
+}
+
+/** One engine's answer to a query, and how long it took. */
+#[derive(Clone, Debug)]
+pub struct Run {
+    pub engine: &'static str,
+    pub solution: Solution,
+    pub elapsed: Duration,
+}
+
+impl Run {
+    fn new(engine: &dyn Engine, query: &Query) -> Result<Self, AgarError> {
+        let stopwatch = Stopwatch::start();
+        let solution = engine.solve(query)?;
+        Ok(Run {
+            engine: engine.name(),
+            solution,
+            elapsed: stopwatch.elapsed(),
+        })
+    }
+}
+
+/** The answers of two engines to the same query, and where they differ. */
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub reference: Run,
+    pub candidate: Run,
+    // anagrams only the reference found, and only the candidate found, with words in sorted order
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+    pub partials_match: bool,
+}
+
+impl Comparison {
+    /**
+     * Whether both searches ran to completion. Budgets cut engines off at different points, so
+     * truncated results are not compared.
+     */
+    pub fn is_complete(&self) -> bool {
+        self.reference.solution.truncated.is_none() && self.candidate.solution.truncated.is_none()
+    }
+
+    /** Whether the engines found the same anagrams and partials. */
+    pub fn agrees(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.partials_match
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for run in [&self.reference, &self.candidate] {
+            write!(
+                f,
+                "{}: {} anagrams in {:?}",
+                run.engine,
+                run.solution.anagrams.len(),
+                run.elapsed
+            )?;
+            if let Some(reason) = run.solution.truncated {
+                write!(f, " (truncated: {})", reason)?;
+            }
+            f.write_str("; ")?;
+        }
+
+        if !self.is_complete() {
+            return f.write_str("not compared");
+        }
+        if self.agrees() {
+            return f.write_str("agree");
+        }
+        let mut problems = Vec::new();
+        if !self.missing.is_empty() {
+            problems.push(format!(
+                "{} missing from {} (first {:?})",
+                self.missing.len(),
+                self.candidate.engine,
+                self.missing[0]
+            ));
+        }
+        if !self.unexpected.is_empty() {
+            problems.push(format!(
+                "{} unexpected from {} (first {:?})",
+                self.unexpected.len(),
+                self.candidate.engine,
+                self.unexpected[0]
+            ));
+        }
+        if !self.partials_match {
+            problems.push("partials differ".to_string());
+        }
+        f.write_str(&problems.join(", "))
+    }
+}
+
+/** An anagram's words in sorted order, so anagrams can be compared regardless of word order. */
+fn sorted_words(anagram: &Anagram) -> Vec<&str> {
+    let mut words = anagram.words.iter().map(String::as_str).collect::<Vec<_>>();
+    words.sort_unstable();
+    words
+}
+
+/** The anagrams in `a` but not in `b`, counting repeats. Both must be sorted. */
+fn difference(a: &[Vec<&str>], b: &[Vec<&str>]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut j = 0;
+    for words in a {
+        while j < b.len() && b[j] < *words {
+            j += 1;
+        }
+        if j < b.len() && b[j] == *words {
+            j += 1;
+        } else {
+            result.push(words.join(" "));
+        }
+    }
+    result
+}
+
+/**
+ * Runs two engines on every query, to validate one against the other and compare their speed.
+ * As an `Engine` it answers with the reference's solution, and fails if the two disagree.
+ */
+pub struct Differential {
+    pub reference: Box<dyn Engine>,
+    pub candidate: Box<dyn Engine>,
+}
+
+impl Differential {
+    pub fn new(reference: Box<dyn Engine>, candidate: Box<dyn Engine>) -> Self {
+        Differential {
+            reference,
+            candidate,
+        }
+    }
+
+    /** Run both engines on a query and report any disagreements. */
+    pub fn compare(&self, query: &Query) -> Result<Comparison, AgarError> {
+        let reference = Run::new(&*self.reference, query)?;
+        let candidate = Run::new(&*self.candidate, query)?;
+
+        let mut comparison = Comparison {
+            missing: Vec::new(),
+            unexpected: Vec::new(),
+            partials_match: true,
+            reference,
+            candidate,
+        };
+        if !comparison.is_complete() {
+            return Ok(comparison);
+        }
+
+        let reference = &comparison.reference.solution;
+        let candidate = &comparison.candidate.solution;
+        let mut expected = reference
+            .anagrams
+            .iter()
+            .map(sorted_words)
+            .collect::<Vec<_>>();
+        let mut found = candidate
+            .anagrams
+            .iter()
+            .map(sorted_words)
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        found.sort_unstable();
+
+        comparison.missing = difference(&expected, &found);
+        comparison.unexpected = difference(&found, &expected);
+        comparison.partials_match = reference.partials == candidate.partials;
+        Ok(comparison)
+    }
+}
+
+impl Engine for Differential {
+    fn name(&self) -> &'static str {
+        "differential"
+    }
+
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        let comparison = self.compare(query)?;
+        if !comparison.agrees() {
+            return Err(AgarError::EnginesDisagree {
+                seed: query.seed.clone(),
+                report: comparison.to_string(),
+            });
+        }
+        Ok(comparison.reference.solution)
+    }
+}
+
+#[cfg(all(test, feature = "trie"))]
+mod tests {
+    use super::*;
+    use crate::tests::{test_dictionary_with, test_solver};
+    use crate::{Budget, Dictionary, TrieEngine, WordList};
+
+    fn query(seed: &str) -> Query {
+        let mut query = Query::new(seed);
+        query.min_length = 1;
+        query.max_num_words = 4;
+        query
+    }
+
+    fn differential(candidate: &WordList) -> Differential {
+        Differential::new(
+            Box::new(test_solver()),
+            Box::new(TrieEngine::new(candidate).unwrap()),
+        )
+    }
+
+    #[test]
+    fn engines_agree() {
+        let differential = differential(&test_dictionary_with(|rank| 1000 - rank as u32));
+        for seed in ["stop a rat", "sprat", "post it", "zzz"] {
+            let comparison = differential.compare(&query(seed)).unwrap();
+            assert!(comparison.is_complete());
+            assert!(comparison.agrees(), "{}", comparison);
+            assert!(comparison.to_string().ends_with("agree"));
+            assert!(differential.solve(&query(seed)).is_ok());
+        }
+    }
+
+    #[test]
+    fn disagreements_are_reported() {
+        // the candidate does not know "rat", but does know "tra"
+        let mut words = test_dictionary_with(|rank| 1000 - rank as u32)
+            .entries()
+            .filter(|&(word, _)| word != "rat")
+            .collect::<WordList>();
+        words.push("tra", 1);
+        let differential = differential(&words);
+
+        let comparison = differential.compare(&query("rat")).unwrap();
+        assert!(comparison.is_complete());
+        assert!(!comparison.agrees());
+        assert_eq!(comparison.missing, ["rat"]);
+        assert_eq!(comparison.unexpected, ["tra"]);
+        assert!(!comparison.partials_match);
+        assert!(matches!(
+            differential.solve(&query("rat")),
+            Err(AgarError::EnginesDisagree { .. })
+        ));
+
+        // truncated searches stop at different points, so they are not compared
+        let mut budgeted = query("stop a rat");
+        budgeted.budget = Budget {
+            max_results: Some(3),
+            ..Budget::default()
+        };
+        let comparison = differential.compare(&budgeted).unwrap();
+        assert!(!comparison.is_complete());
+        assert!(comparison.to_string().ends_with("not compared"));
+    }
+}
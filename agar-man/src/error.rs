@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+/** Errors from building a dictionary or running a query. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AgarError {
+    // only lowercase ASCII letters can be anagrammed
+    InvalidCharacter { word: String, character: char },
+    // a word with no letters, such as one that was only whitespace
+    EmptyWord,
+    // a letter occurs more often than a counter can hold
+    TooManyLetters { letter: char, max_count: usize },
+    InvalidDictionaryLine { line_number: usize, line: String },
+    InvalidUtf8 { position: usize },
+    InvalidBigramLine { line_number: usize, line: String },
+    // a grid for `grid_solve` that is not `size` rows of `size` letters
+    InvalidGrid { rows: Vec<String>, size: usize },
+    // a saved index that is corrupt or from an incompatible version
+    InvalidIndex { reason: String },
+    // a resume token that is corrupt, or from a different query or dictionary
+    InvalidResumeToken { reason: String },
+    // a scorer name or blend that `parse_scorer` does not understand
+    InvalidScorer { spec: String, reason: String },
+    // a `Differential` whose engines found different anagrams
+    EnginesDisagree { seed: String, report: String },
+    // io::Error is neither Clone nor Eq, so only its message is kept
+    Io { path: String, message: String },
+}
+
+impl fmt::Display for AgarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgarError::InvalidCharacter { word, character } => {
+                write!(f, "invalid character {:?} in {:?}", character, word)
+            }
+            AgarError::EmptyWord => f.write_str("words must have at least one letter"),
+            AgarError::TooManyLetters { letter, max_count } => {
+                write!(f, "more than {} {:?}s in the seed", max_count, letter)
+            }
+            AgarError::InvalidDictionaryLine { line_number, line } => {
+                write!(
+                    f,
+                    "expected `word<TAB>count` on dictionary line {}, got {:?}",
+                    line_number, line
+                )
+            }
+            AgarError::InvalidBigramLine { line_number, line } => {
+                write!(
+                    f,
+                    "expected `first<TAB>second<TAB>count` on bigram line {}, got {:?}",
+                    line_number, line
+                )
+            }
+            AgarError::InvalidUtf8 { position } => {
+                write!(f, "dictionary is not valid UTF-8 after byte {}", position)
+            }
+            AgarError::InvalidGrid { rows, size } => write!(
+                f,
+                "expected {} rows of {} letters, got {:?}",
+                size, size, rows
+            ),
+            AgarError::InvalidIndex { reason } => write!(f, "invalid index: {}", reason),
+            AgarError::InvalidResumeToken { reason } => {
+                write!(f, "invalid resume token: {}", reason)
+            }
+            AgarError::InvalidScorer { spec, reason } => {
+                write!(f, "invalid scorer {:?}: {}", spec, reason)
+            }
+            AgarError::EnginesDisagree { seed, report } => {
+                write!(f, "engines disagree on {:?}: {}", seed, report)
+            }
+            AgarError::Io { path, message } => write!(f, "could not read {}: {}", path, message),
+        }
+    }
+}
+
+impl Error for AgarError {}
+
+impl From<AgarError> for JsValue {
+    fn from(error: AgarError) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
@@ -1,10 +1,13 @@
-fn filter_grid_word(word: &String, grid_letters: &Vec<char>, min_length: usize) -> bool {
-    word.chars().all(char::is_alphanumeric)
-        & (word.chars().count() >= min_length)
+use crate::{contained, normalize_word, AgarError, Dictionary};
+use std::collections::HashSet;
+
+fn filter_grid_word(word: &str, grid_letters: &[char], min_length: usize) -> bool {
+    word.chars().all(|c| c.is_ascii_lowercase())
+        & (word.chars().count() >= min_length.max(1))
         & contained(word, &grid_letters.iter().collect::<String>())
 }
 
-const GRID_SIZE: usize = 4;
+pub const GRID_SIZE: usize = 4;
 const NUM_NODES: usize = GRID_SIZE * GRID_SIZE;
 
 fn dfs(
@@ -30,21 +33,38 @@ fn dfs(
 
         let neighbor_char = grid[neighbor / GRID_SIZE][neighbor % GRID_SIZE];
 
-        if next_char == neighbor_char {
-            if dfs(grid, adj_matrix, neighbor, visited, next_word) {
-                return true;
-            }
+        if next_char == neighbor_char && dfs(grid, adj_matrix, neighbor, visited, next_word) {
+            return true;
         }
     }
 
     visited[current_node] = false;
-    return false;
+    false
 }
 
-fn solve_grid() {
-    let mut grid_strs = ["vgne", "optm", "alia", "irrh"];
+/**
+ * Find the dictionary words that can be traced through a Boggle-style grid, moving between
+ * adjacent (including diagonal) cells without reusing one. `rows` holds `GRID_SIZE` strings of
+ * `GRID_SIZE` letters each. Results are sorted by length.
+ */
+pub fn grid_solve(
+    dictionary: &dyn Dictionary,
+    rows: &[&str],
+    min_length: usize,
+) -> Result<Vec<String>, AgarError> {
     let mut grid = [[' '; GRID_SIZE]; GRID_SIZE];
-    for (i, row) in grid_strs.iter().enumerate() {
+    let invalid_grid = || AgarError::InvalidGrid {
+        rows: rows.iter().map(|row| row.to_string()).collect(),
+        size: GRID_SIZE,
+    };
+    if rows.len() != GRID_SIZE {
+        return Err(invalid_grid());
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let row = row.to_lowercase();
+        if row.chars().count() != GRID_SIZE || !row.chars().all(|c| c.is_ascii_lowercase()) {
+            return Err(invalid_grid());
+        }
         for (j, c) in row.chars().enumerate() {
             grid[i][j] = c;
         }
@@ -52,7 +72,8 @@ fn solve_grid() {
 
     let mut graph = [[false; NUM_NODES]; NUM_NODES];
 
-    // fill in graph
+    // fill in graph, both ways round
+    #[allow(clippy::needless_range_loop)]
     for i in 0..NUM_NODES {
         let row = i / GRID_SIZE;
         let col = i % GRID_SIZE;
@@ -85,71 +106,97 @@ fn solve_grid() {
         }
     }
 
-    // // print out graph
-    // for i in 0..NUM_NODES {
-    //     let letter = grid[i / GRID_SIZE][i % GRID_SIZE];
-    //     print!("{}: ", letter);
-
-    //     for j in 0..NUM_NODES {
-    //         // print!("{}", graph[i][j] as u8);
-
-    //         if graph[i][j] {
-    //             let next_letter = grid[j / GRID_SIZE][j % GRID_SIZE];
-    //             print!("{} ", next_letter);
-    //         }
-    //     }
-    //     println!();
-    // }
-
-    let dictionary = include_str!("dictionary.txt");
-    let lines: Vec<String> = dictionary.split("\n").map(str::to_string).collect();
-
     let grid_chars = grid
         .iter()
         .flat_map(|row| row.iter())
-        .map(|c| *c)
+        .copied()
         .collect::<Vec<char>>();
 
-    let filter_line_closure = |word: &String| filter_grid_word(word, &grid_chars, 3);
-
-    let processed_lines: Vec<String> = lines // using String as the return type of `to_lowercase`
-        .iter()
-        .map(process_line)
-        .filter(filter_line_closure)
-        .collect();
-
-    let words_set = processed_lines.iter().cloned().collect::<HashSet<String>>();
+    let words_set = dictionary
+        .entries()
+        .map(|(word, _)| normalize_word(word))
+        .filter(|word| filter_grid_word(word, &grid_chars, min_length))
+        .collect::<HashSet<String>>();
 
-    let mut results: HashSet<String> = HashSet::new();
+    let mut results_vec = Vec::new();
 
     // check if each word in the dictionary is a valid path
-    for word in &words_set {
-        let mut found = false;
-        // println!("Checking {}", word);
+    for word in words_set {
+        let first_char = word.chars().next().unwrap_or(' ');
 
         // check if there is a path in the graph that spells out the word
-        for i in 0..NUM_NODES {
+        let found = (0..NUM_NODES).any(|i| {
             let mut visited = [false; NUM_NODES];
-            if dfs(&grid, &graph, i, &mut visited, word) {
-                found = true;
-                break;
-            }
-        }
+            grid_chars[i] == first_char && dfs(&grid, &graph, i, &mut visited, &word[1..])
+        });
 
         if found {
-            results.insert(word.clone());
+            results_vec.push(word);
         }
     }
 
-    // println!("Results: {}", results.len());
-    // for result in results {
-    //     println!("{}", result);
-    // }
     // sort by length
-    let mut results_vec = results.into_iter().collect::<Vec<String>>();
-    results_vec.sort_by(|a, b| a.len().cmp(&b.len()));
+    results_vec.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+    Ok(results_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WordList;
+
+    // two a's, in opposite corners
+    const ROWS: [&str; GRID_SIZE] = ["abcd", "efgh", "ijkl", "mnoa"];
 
-    for result in results_vec {
-        println!("{}", result);
+    fn dictionary(words: &[&str]) -> WordList {
+        words.iter().map(|&word| (word, 1)).collect()
+    }
+
+    #[test]
+    fn words_follow_adjacent_cells() {
+        let dictionary = dictionary(&[
+            // across, down and diagonally both ways
+            "abc", "aei", "afk", "dgj", "ab",
+            // "c" is not next to "a", the "a" of "aba" would have to be used twice, and "hello"
+            // and "don't" are not in the grid at all
+            "ace", "aba", "hello", "don't",
+        ]);
+        assert_eq!(
+            grid_solve(&dictionary, &ROWS, 3).unwrap(),
+            ["abc", "aei", "afk", "dgj"]
+        );
+        // shorter words come first
+        assert_eq!(
+            grid_solve(&dictionary, &ROWS, 2).unwrap(),
+            ["ab", "abc", "aei", "afk", "dgj"]
+        );
+        // the letters are lowercased
+        let upper = ROWS.map(str::to_uppercase);
+        let upper = upper.iter().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(
+            grid_solve(&dictionary, &upper, 2).unwrap(),
+            ["ab", "abc", "aei", "afk", "dgj"]
+        );
+    }
+
+    #[test]
+    fn invalid_grids_are_rejected() {
+        let dictionary = dictionary(&["abc"]);
+        for rows in [
+            &["abc", "efgh", "ijkl", "mnop"][..],
+            &["abcd", "efgh", "ijkl"],
+            &["abcd", "efgh", "ijkl", "mnop", "qrst"],
+            &["ab1d", "efgh", "ijkl", "mnop"],
+            &["ab d", "efgh", "ijkl", "mnop"],
+        ] {
+            assert!(matches!(
+                grid_solve(&dictionary, rows, 3),
+                Err(AgarError::InvalidGrid {
+                    size: GRID_SIZE,
+                    ..
+                })
+            ));
+        }
     }
 }
@@ -0,0 +1,690 @@
+// The preprocessed dictionary as plain data, and its binary encoding. build.rs compiles this
+// file too, to precompute the index of the built-in dictionary, so its dependencies must also be
+// build-dependencies.
+
+use rustc_hash::FxHashSet;
+use std::borrow::Cow;
+
+pub const ALPHA_SIZE: usize = 26; // a-z
+
+// wide enough for seeds made of whole sentences
+pub type Count = u16;
+pub type Counter = [Count; ALPHA_SIZE];
+// dense id of a distinct letter multiset
+pub type WordKey = u32;
+
+const MAGIC: &[u8; 4] = b"AGAR";
+// bump whenever the encoding changes, so indexes written by older builds are rejected
+pub const VERSION: u32 = 5;
+
+/** A tree of letter multisets, branching on the count of each letter in turn. */
+#[derive(Clone, Debug, Default)]
+pub struct CounterNode {
+    pub key: Option<WordKey>,
+    pub children: Vec<(Count, CounterNode)>,
+}
+
+impl CounterNode {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /** Insert a counter and its key, returning false if the counter is already present. */
+    pub fn insert(&mut self, counter: &Counter, counter_key: WordKey, index: usize) -> bool {
+        // the counts after the last letter are all 0, so the key goes on the node for it
+        let end = counter
+            .iter()
+            .rposition(|&count| count > 0)
+            .map_or(0, |i| i + 1);
+        self.insert_until(counter, counter_key, index, end)
+    }
+
+    fn insert_until(
+        &mut self,
+        counter: &Counter,
+        counter_key: WordKey,
+        index: usize,
+        end: usize,
+    ) -> bool {
+        if index >= end {
+            if self.key.is_some() {
+                return false;
+            }
+            self.key = Some(counter_key);
+            return true;
+        }
+
+        let count = counter[index];
+
+        // keep the children sorted, so that lookups can stop early
+        let position = self.children.partition_point(|(j, _)| *j < count);
+        match self.children.get_mut(position) {
+            Some((j, child)) if *j == count => {
+                child.insert_until(counter, counter_key, index + 1, end)
+            }
+            _ => {
+                let mut new = CounterNode::new();
+                let inserted = new.insert_until(counter, counter_key, index + 1, end);
+                self.children.insert(position, (count, new));
+                inserted
+            }
+        }
+    }
+
+    /**
+     * Build a tree from distinct counters in ascending order, which is the tree's pre-order, so
+     * each new node is the last child of a node on the path to the previous one.
+     */
+    pub fn from_sorted<'c>(counters: impl Iterator<Item = (&'c Counter, WordKey)>) -> Self {
+        // the path to the last node added, with the count each node branched on
+        let mut path = vec![(0, CounterNode::new())];
+        for (counter, key) in counters {
+            let end = counter
+                .iter()
+                .rposition(|&count| count > 0)
+                .map_or(0, |i| i + 1);
+            let shared = path[1..]
+                .iter()
+                .zip(&counter[..end])
+                .take_while(|((count, _), other)| count == *other)
+                .count();
+            while path.len() > shared + 1 {
+                let (count, node) = path.pop().expect("the root is never popped");
+                let parent = &mut path.last_mut().expect("the root is never popped").1;
+                parent.children.push((count, node));
+            }
+            path.extend(
+                counter[shared..end]
+                    .iter()
+                    .map(|&count| (count, CounterNode::new())),
+            );
+            path.last_mut().expect("the root is never popped").1.key = Some(key);
+        }
+        while path.len() > 1 {
+            let (count, node) = path.pop().expect("the root is never popped");
+            let parent = &mut path.last_mut().expect("the root is never popped").1;
+            parent.children.push((count, node));
+        }
+        path.pop().expect("the root is never popped").1
+    }
+}
+
+pub struct Layout<'a> {
+    // the words, one per line: by id when built, grouped by key when decoded, which borrows them
+    pub text: Cow<'a, str>,
+    // where each word starts and ends in `text`, by id
+    pub spans: Vec<(usize, usize)>,
+    pub counts: Vec<u32>,
+    // words after the first `ranked` were added at runtime
+    pub ranked: usize,
+    pub index_map: [usize; ALPHA_SIZE],
+    // the ids of the words spelling each letter multiset and that multiset, in key order
+    pub keys: Vec<Vec<usize>>,
+    pub counters: Vec<Counter>,
+    pub root: CounterNode,
+}
+
+/** Map letters to their rank by frequency, so the counter tree branches on common letters first. */
+pub fn rank_letters<'a>(words: impl Iterator<Item = &'a str>) -> [usize; ALPHA_SIZE] {
+    let mut letter_frequencies = [0; ALPHA_SIZE];
+    for word in words {
+        for b in word.bytes().filter(|&b| b != b' ') {
+            letter_frequencies[(b - b'a') as usize] += 1;
+        }
+    }
+
+    // argsort letter_frequencies
+    let mut sorted_indices: Vec<usize> = (0..ALPHA_SIZE).collect();
+    sorted_indices.sort_by(|a, b| letter_frequencies[*b].cmp(&letter_frequencies[*a]));
+
+    // map indices to sorted indices
+    let mut index_map = [0; ALPHA_SIZE];
+    for (i, j) in sorted_indices.iter().enumerate() {
+        index_map[*j] = i;
+    }
+    index_map
+}
+
+/** Lowercase an entry and collapse its whitespace, so phrases like "new york" keep one space. */
+pub fn normalize_word(word: &str) -> String {
+    word.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/** The first character of a word that is neither a letter a-z nor a space between words. */
+pub fn invalid_character(word: &str) -> Option<char> {
+    word.chars().find(|&c| !c.is_ascii_lowercase() && c != ' ')
+}
+
+/** Split a `word\tcount` line, counting 0 if the count is missing, or `None` if it is invalid. */
+pub fn parse_entry(line: &str) -> Option<(&str, u32)> {
+    match line.split_once('\t') {
+        Some((word, count)) => Some((word.trim(), count.trim().parse().ok()?)),
+        None => Some((line.trim(), 0)),
+    }
+}
+
+/**
+ * Normalise the entries, keep the first of each word and rank them by count, keeping the
+ * dictionary's own order for ties. Words that `invalid_character` rejects, such as "don't" or
+ * "café", are skipped rather than failing the whole list, and returned separately.
+ */
+pub fn rank_words<'a>(
+    entries: impl Iterator<Item = (&'a str, u32)>,
+) -> (Vec<(String, u32)>, Vec<String>) {
+    let mut seen = FxHashSet::default();
+    let mut word_counts = Vec::new();
+    let mut skipped = Vec::new();
+    for (word, count) in entries {
+        let word = normalize_word(word);
+        if invalid_character(&word).is_some() {
+            skipped.push(word);
+        } else if !word.is_empty() && seen.insert(word.clone()) {
+            word_counts.push((word, count));
+        }
+    }
+    word_counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    (word_counts, skipped)
+}
+
+/** Put each word on its own line, returning the text and where each word starts and ends. */
+fn join_words<'w>(words: impl ExactSizeIterator<Item = &'w str>) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(words.len());
+    for word in words {
+        spans.push((text.len(), text.len() + word.len()));
+        text.push_str(word);
+        text.push('\n');
+    }
+    (text, spans)
+}
+
+impl<'a> Layout<'a> {
+    /** Lay out ranked words, grouping them by counter; see `assemble`. */
+    pub fn new(
+        words: Vec<String>,
+        counts: Vec<u32>,
+        index_map: [usize; ALPHA_SIZE],
+        counters: &[Counter],
+    ) -> Layout<'static> {
+        // a stable sort by counter puts each group's words together, in id order
+        let mut ids = (0..counters.len()).collect::<Vec<_>>();
+        ids.sort_by_key(|&id| &counters[id]);
+        let groups = ids
+            .chunk_by(|&a, &b| counters[a] == counters[b])
+            .map(|group| (counters[group[0]], group.to_vec()))
+            .collect();
+
+        let (text, spans) = join_words(words.iter().map(String::as_str));
+        let ranked = words.len();
+        Layout::assemble(Cow::Owned(text), spans, counts, ranked, index_map, groups)
+    }
+
+    /**
+     * Number groups of words given in counter order by (length, counter), so the longest words
+     * are tried first. Counting the groups of each length is enough to place them, and the tree
+     * is built in the order given; neither needs a sort.
+     */
+    fn assemble(
+        text: Cow<'a, str>,
+        spans: Vec<(usize, usize)>,
+        counts: Vec<u32>,
+        ranked: usize,
+        index_map: [usize; ALPHA_SIZE],
+        groups: Vec<(Counter, Vec<usize>)>,
+    ) -> Layout<'a> {
+        let lengths = groups
+            .iter()
+            .map(|(counter, _)| counter_length(counter))
+            .collect::<Vec<_>>();
+        let mut next_keys = vec![0; lengths.iter().max().map_or(0, |&length| length + 1)];
+        for &length in &lengths {
+            next_keys[length] += 1;
+        }
+        let mut first_key = 0;
+        for next_key in next_keys.iter_mut() {
+            (*next_key, first_key) = (first_key, first_key + *next_key);
+        }
+        let group_keys = lengths
+            .iter()
+            .map(|&length| {
+                next_keys[length] += 1;
+                next_keys[length] - 1
+            })
+            .collect::<Vec<_>>();
+
+        let root = CounterNode::from_sorted(
+            groups
+                .iter()
+                .zip(&group_keys)
+                .map(|((counter, _), &key)| (counter, key as WordKey)),
+        );
+
+        let mut keys = vec![Vec::new(); groups.len()];
+        let mut counters = vec![[0; ALPHA_SIZE]; groups.len()];
+        for ((counter, ids), key) in groups.into_iter().zip(group_keys) {
+            keys[key] = ids;
+            counters[key] = counter;
+        }
+
+        Layout {
+            text,
+            spans,
+            counts,
+            ranked,
+            index_map,
+            keys,
+            counters,
+            root,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn word(&self, id: usize) -> &str {
+        let (start, end) = self.spans[id];
+        &self.text[start..end]
+    }
+
+    // the solver saves from its `Index`; only build.rs and the tests encode whole layouts
+    #[allow(dead_code)]
+    pub fn encode(&self) -> Vec<u8> {
+        encode(
+            |id| self.word(id),
+            &self.counts,
+            self.ranked,
+            &self.index_map,
+            &self.keys,
+            &self.counters,
+        )
+    }
+
+    /**
+     * Decode an index written by `encode`, describing what is wrong if it cannot. The words are
+     * borrowed from `bytes`, and come already grouped by key in counter order, so nothing is
+     * sorted and only the first word of each key has its letters counted.
+     */
+    pub fn decode(bytes: &'a [u8]) -> Result<Layout<'a>, String> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("not an agar-man index".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!(
+                "index format version {}, expected {}",
+                version, VERSION
+            ));
+        }
+
+        let num_words = reader.u32()? as usize;
+        let ranked = reader.u32()? as usize;
+        let num_keys = reader.u32()? as usize;
+        let text_length = reader.u32()? as usize;
+        if ranked > num_words {
+            return Err("ranked word count does not match the header".to_string());
+        }
+
+        let mut index_map = [0; ALPHA_SIZE];
+        for (i, &j) in reader.take(ALPHA_SIZE)?.iter().enumerate() {
+            index_map[i] = j as usize;
+        }
+        let mut sorted = index_map;
+        sorted.sort_unstable();
+        if sorted.iter().enumerate().any(|(i, &j)| i != j) {
+            return Err("index map is not a permutation".to_string());
+        }
+
+        let text = std::str::from_utf8(reader.take(text_length)?)
+            .map_err(|_| "words are not valid UTF-8".to_string())?;
+        if let Some(b) = text
+            .bytes()
+            .find(|&b| !b.is_ascii_lowercase() && b != b' ' && b != b'\n')
+        {
+            return Err(format!("{:?} is not a letter", b as char));
+        }
+        // where each word starts and ends in `text`, in the order they are written
+        let mut lines = Vec::with_capacity(num_words.min(text_length));
+        let mut start = 0;
+        for (i, b) in text.bytes().enumerate() {
+            if b == b'\n' {
+                if i == start {
+                    return Err("index has an empty word".to_string());
+                }
+                lines.push((start, i));
+                start = i + 1;
+            }
+        }
+        if lines.len() != num_words || start != text.len() {
+            return Err("word count does not match the header".to_string());
+        }
+
+        let mut group_sizes = Vec::with_capacity(num_keys.min(num_words));
+        let mut total = 0usize;
+        for _ in 0..num_keys {
+            let size = reader.varint()? as usize;
+            total = total.saturating_add(size);
+            if size == 0 || total > num_words {
+                return Err("key groups do not match the word count".to_string());
+            }
+            group_sizes.push(size);
+        }
+        if total != num_words {
+            return Err("key groups do not match the word count".to_string());
+        }
+
+        // the id of each word, in the order they are written
+        let mut spans = vec![(0, 0); num_words];
+        let mut seen = vec![false; num_words];
+        let mut ids = Vec::with_capacity(num_words);
+        for &line in &lines {
+            let id = reader.varint()? as usize;
+            if id >= num_words || std::mem::replace(&mut seen[id], true) {
+                return Err("word ids are not a permutation".to_string());
+            }
+            spans[id] = line;
+            ids.push(id);
+        }
+
+        let counts = (0..num_words)
+            .map(|_| reader.varint())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if reader.position != bytes.len() {
+            return Err("trailing bytes after the index".to_string());
+        }
+
+        let mut groups: Vec<(Counter, Vec<usize>)> = Vec::with_capacity(num_keys);
+        let mut rest = &ids[..];
+        for size in group_sizes {
+            let (group, after) = rest.split_at(size);
+            rest = after;
+            if group.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err("a key's word ids are not ascending".to_string());
+            }
+            let (start, end) = spans[group[0]];
+            let word = &text[start..end];
+            let counter =
+                counter_of(word, &index_map).map_err(|_| format!("{:?} is too long", word))?;
+            // strictly ascending also rules out a multiset spelled by two keys
+            if groups.last().is_some_and(|(last, _)| *last >= counter) {
+                return Err("keys are not in counter order".to_string());
+            }
+            groups.push((counter, group.to_vec()));
+        }
+
+        Ok(Layout::assemble(
+            Cow::Borrowed(text),
+            spans,
+            counts,
+            ranked,
+            index_map,
+            groups,
+        ))
+    }
+
+    /** Decode an index that is no longer needed, reusing its buffer for the words. */
+    pub fn decode_owned(bytes: Vec<u8>) -> Result<Layout<'static>, String> {
+        let Layout {
+            text,
+            spans,
+            counts,
+            ranked,
+            index_map,
+            keys,
+            counters,
+            root,
+        } = Layout::decode(&bytes)?;
+        let start = text.as_ptr() as usize - bytes.as_ptr() as usize;
+        let end = start + text.len();
+
+        // shift the words to the front of the buffer in place, rather than copying them out
+        let mut bytes = bytes;
+        bytes.truncate(end);
+        bytes.drain(..start);
+        let text = String::from_utf8(bytes).map_err(|_| "words are not valid UTF-8".to_string())?;
+
+        Ok(Layout {
+            text: Cow::Owned(text),
+            spans,
+            counts,
+            ranked,
+            index_map,
+            keys,
+            counters,
+            root,
+        })
+    }
+}
+
+/**
+ * The magic and version, then little-endian u32 word count, `ranked`, key count and text length,
+ * and the index map. Then the words one per line, grouped by key with the keys in counter order,
+ * and as varints: the number of words of each key, the id of each word as written, and the word
+ * counts by id. Only the first word of a key is counted when decoding; keys without words are
+ * left out, so the ids of words that were removed must be too.
+ */
+pub fn encode<'w>(
+    word: impl Fn(usize) -> &'w str,
+    counts: &[u32],
+    ranked: usize,
+    index_map: &[usize; ALPHA_SIZE],
+    keys: &[Vec<usize>],
+    counters: &[Counter],
+) -> Vec<u8> {
+    let mut order = (0..keys.len())
+        .filter(|&key| !keys[key].is_empty())
+        .collect::<Vec<_>>();
+    order.sort_unstable_by_key(|&key| &counters[key]);
+    let ids = || order.iter().flat_map(|&key| &keys[key]).copied();
+    let text_length = ids().map(|id| word(id).len() + 1).sum::<usize>();
+
+    let mut bytes =
+        Vec::with_capacity(MAGIC.len() + 20 + ALPHA_SIZE + text_length + 5 * counts.len());
+    bytes.extend_from_slice(MAGIC);
+    for value in [
+        VERSION,
+        counts.len() as u32,
+        ranked as u32,
+        order.len() as u32,
+        text_length as u32,
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend(index_map.iter().map(|&i| i as u8));
+    for id in ids() {
+        bytes.extend_from_slice(word(id).as_bytes());
+        bytes.push(b'\n');
+    }
+    for &key in &order {
+        write_varint(&mut bytes, keys[key].len() as u32);
+    }
+    for id in ids() {
+        write_varint(&mut bytes, id as u32);
+    }
+    for &count in counts {
+        write_varint(&mut bytes, count);
+    }
+    bytes
+}
+
+fn counter_length(counter: &Counter) -> usize {
+    counter.iter().map(|&count| count as usize).sum()
+}
+
+/**
+ * The letters of a word, ignoring spaces, in the order of `index_map`. Fails with a letter that
+ * occurs too often to count; the word must already have passed `invalid_character`.
+ */
+pub fn counter_of(word: &str, index_map: &[usize; ALPHA_SIZE]) -> Result<Counter, char> {
+    let mut counter: Counter = [0; ALPHA_SIZE];
+    for b in word.bytes().filter(|&b| b != b' ') {
+        let count = &mut counter[index_map[(b - b'a') as usize]];
+        *count = count.checked_add(1).ok_or(b as char)?;
+    }
+    Ok(counter)
+}
+
+/** LEB128: seven bits per byte, with the high bit set on all but the last. */
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "index is truncated".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint is too long".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(entries: &str) -> Layout<'static> {
+        let (word_counts, _) = rank_words(entries.lines().map(|line| parse_entry(line).unwrap()));
+        let index_map = rank_letters(word_counts.iter().map(|(word, _)| word.as_str()));
+        let counters = word_counts
+            .iter()
+            .map(|(word, _)| counter_of(word, &index_map).unwrap())
+            .collect::<Vec<_>>();
+        let (words, counts) = word_counts.into_iter().unzip();
+        Layout::new(words, counts, index_map, &counters)
+    }
+
+    #[test]
+    fn entries_are_normalised_and_ranked() {
+        let entries = "stop\t5\n  New   York \t9\nSTOP\t7\nrat";
+        let (word_counts, skipped) =
+            rank_words(entries.lines().map(|line| parse_entry(line).unwrap()));
+        assert!(skipped.is_empty());
+        assert_eq!(
+            word_counts,
+            [
+                ("new york".to_string(), 9),
+                ("stop".to_string(), 5),
+                ("rat".to_string(), 0)
+            ]
+        );
+        assert_eq!(parse_entry("stop\tmany"), None);
+        assert_eq!(
+            rank_words([("café", 1), ("stop", 2), ("Don't", 3)].into_iter()),
+            (
+                vec![("stop".to_string(), 2)],
+                vec!["café".to_string(), "don't".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let layout = layout("stop\t9\npost\t8\nnew york\t7\nrat\t6\ntar\t5\na\t4\nzzz\t3");
+        let bytes = layout.encode();
+        let decoded = Layout::decode(&bytes).unwrap();
+
+        // the words are grouped by key, with the keys in counter order, but keep their ids
+        assert_eq!(decoded.text, "zzz\nnew york\na\nstop\npost\nrat\ntar\n");
+        for id in 0..layout.len() {
+            assert_eq!(decoded.word(id), layout.word(id));
+        }
+        assert_eq!(decoded.word(2), "new york");
+        assert_eq!(decoded.counts, layout.counts);
+        assert_eq!(decoded.ranked, layout.ranked);
+        assert_eq!(decoded.index_map, layout.index_map);
+        assert_eq!(decoded.keys, layout.keys);
+        assert_eq!(decoded.counters, layout.counters);
+        // building the tree in one pass gives the same tree as inserting each counter
+        let mut inserted = CounterNode::new();
+        for (key, counter) in layout.counters.iter().enumerate() {
+            inserted.insert(counter, key as WordKey, 0);
+        }
+        assert_eq!(format!("{:?}", decoded.root), format!("{:?}", inserted));
+        assert_eq!(decoded.encode(), bytes);
+
+        // an owned index keeps its buffer for the words
+        let buffer = bytes.clone();
+        let address = buffer.as_ptr();
+        let owned = Layout::decode_owned(buffer).unwrap();
+        assert!(matches!(owned.text, Cow::Owned(_)));
+        assert_eq!(owned.text, decoded.text);
+        assert_eq!(owned.text.as_ptr(), address);
+        assert_eq!(owned.word(2), "new york");
+        assert_eq!(owned.keys, layout.keys);
+    }
+
+    #[test]
+    fn empty_round_trip() {
+        let bytes = layout("").encode();
+        let decoded = Layout::decode(&bytes).unwrap();
+        assert_eq!(decoded.len(), 0);
+        assert!(decoded.keys.is_empty());
+    }
+
+    #[test]
+    fn stale_versions_are_rejected() {
+        let mut bytes = layout("stop\t9\nrat\t6").encode();
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(VERSION - 1).to_le_bytes());
+        let error = Layout::decode(&bytes).err().unwrap();
+        assert!(error.contains("version"), "{}", error);
+    }
+
+    #[test]
+    fn corrupt_indexes_are_rejected() {
+        let bytes = layout("stop\t9\npost\t8\nrat\t6").encode();
+        assert!(Layout::decode(b"hello").is_err());
+        for length in 0..bytes.len() {
+            assert!(Layout::decode(&bytes[..length]).is_err());
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Layout::decode(&trailing).is_err());
+        // the words are checked as they are split
+        let text_start = MAGIC.len() + 20 + ALPHA_SIZE;
+        for (at, byte) in [
+            (text_start, b'!'),
+            (text_start, b'\n'),
+            (text_start + 3, b'a'),
+        ] {
+            let mut corrupt = bytes.clone();
+            corrupt[at] = byte;
+            assert!(Layout::decode(&corrupt).is_err());
+        }
+    }
+}
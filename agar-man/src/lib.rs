@@ -1,95 +1,94 @@
 extern crate js_sys;
 extern crate wasm_bindgen;
-use itertools::Itertools;
-use js_sys::Array;
-use rustc_hash::{FxHashMap, FxHasher};
-use std::collections::{HashMap, HashSet};
-use std::hash::BuildHasherDefault;
+use flate2::read::GzDecoder;
+use num_bigint::BigUint;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
 use std::str;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
+mod anagrams;
+mod budget;
+mod dictionary;
+#[cfg(feature = "prep")]
+pub mod dictionary_prep;
+mod engine;
+mod error;
+#[cfg(feature = "grid")]
+mod grid_solve;
+mod layout;
+mod resume;
+mod sample;
+mod score;
+#[cfg(feature = "trie")]
+mod trie_solve;
+
+pub use anagrams::Anagrams;
+pub use budget::{Budget, CancellationToken, Truncation};
+pub use dictionary::{Bigrams, Dictionary, WordList};
+#[cfg(not(target_arch = "wasm32"))]
+pub use engine::ParallelSolver;
+pub use engine::{Comparison, Differential, Engine, Run};
+pub use error::AgarError;
+#[cfg(feature = "grid")]
+pub use grid_solve::{grid_solve, GRID_SIZE};
+pub use layout::normalize_word;
+pub use sample::Sampling;
+pub use score::{
+    parse_scorer, BigramScorer, Blend, FewerWords, GeometricMean, LongestWord, Mean, MinCount,
+    Scorer,
+};
+#[cfg(feature = "trie")]
+pub use trie_solve::{trie_solve, TrieEngine};
+
+use layout::{Count, Counter, CounterNode, Layout, WordKey, ALPHA_SIZE};
+use resume::Fingerprint;
+
 // #[global_allocator]
 // static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-fn contained(smaller: &str, larger: &str) -> bool {
-    // speed up by checking characters first
-    let contains_letters = smaller.chars().all(|c| larger.contains(c));
-
-    if !contains_letters {
-        return false;
-    }
-
-    let smaller_counts = to_counter(smaller);
-    let larger_counts = to_counter(larger);
-
-    return counter_contains(&larger_counts, &smaller_counts);
-}
-
-fn filter_line(line: &String, seed: &str, min_length: usize, excludes: &HashSet<String>) -> bool {
-    line.chars().all(char::is_alphanumeric)
-        & (line.len() >= min_length)
-        & contained(line, seed)
-        & !excludes.contains(&line.to_lowercase())
-}
-
-const ALPHA_SIZE: usize = 26; // a-z
 const ASCII_OFFSET: usize = 97; // a's ASCII code.
-const MAX_WORD_LENGTH: usize = 16;
-
-type EncodedWord = [i8; MAX_WORD_LENGTH];
-type Counter = [i8; ALPHA_SIZE];
-type WordProduct = u64;
 
 /** Convert an ASCII char into an usize, such that 'a' -> 0, 'b' -> 1, ..., 'z' -> 25. */
 fn to_index(c: char) -> usize {
     (c as usize) - ASCII_OFFSET
 }
 
-fn to_index_i8(c: char) -> i8 {
-    ((c as usize) - ASCII_OFFSET) as i8
-}
-
-fn encode_word(word: &str) -> EncodedWord {
-    let mut encoded_word = [-1; MAX_WORD_LENGTH];
-    for (i, c) in word.chars().enumerate() {
-        encoded_word[i] = to_index_i8(c);
+/** Check that a word only contains letters `to_index` can handle, or spaces between words. */
+fn check_word(word: &str) -> Result<(), AgarError> {
+    match layout::invalid_character(word) {
+        Some(character) => Err(AgarError::InvalidCharacter {
+            word: word.to_string(),
+            character,
+        }),
+        None => Ok(()),
     }
-    encoded_word
 }
 
-fn decode_and_extend_word(encoded_word: &EncodedWord, string: &mut String) {
-    for i in 0..encoded_word.len() {
-        let c = encoded_word[i];
-        if c == -1 {
-            break;
-        }
-        string.push(to_char(c as usize));
-    }
-    string.push(' ');
+fn to_counter(s: &str) -> Result<Counter, AgarError> {
+    let identity = std::array::from_fn(|i| i);
+    to_counter_indexed(s, &identity)
 }
 
-/** Convert a usize to an ASCII char, such that 0 -> 'a', 1 -> 'b', ..., 25 -> 'z' */
-fn to_char(i: usize) -> char {
-    (i + ASCII_OFFSET) as u8 as char
+fn to_counter_indexed(s: &str, indices: &[usize; ALPHA_SIZE]) -> Result<Counter, AgarError> {
+    check_word(s)?;
+    layout::counter_of(s, indices).map_err(|letter| AgarError::TooManyLetters {
+        letter,
+        max_count: Count::MAX as usize,
+    })
 }
 
-fn to_counter(s: &str) -> Counter {
-    let mut counts: Counter = [0; ALPHA_SIZE];
-    for c in s.chars() {
-        let i = to_index(c);
-        counts[i] += 1;
-    }
-    counts
+fn letter_count(word: &str) -> usize {
+    word.bytes().filter(|&b| b != b' ').count()
 }
 
-fn to_counter_indexed(s: &str, indices: &[usize; ALPHA_SIZE]) -> Counter {
-    let mut counts: Counter = [0; ALPHA_SIZE];
-    for c in s.chars() {
-        let i = indices[to_index(c)];
-        counts[i] += 1;
-    }
-    counts
+fn counter_length(counter: &Counter) -> usize {
+    counter.iter().map(|&count| count as usize).sum()
 }
 
 fn add_counters(a: &mut Counter, b: &Counter) {
@@ -113,62 +112,37 @@ fn counter_contains(a: &Counter, b: &Counter) -> bool {
     true
 }
 
-fn is_partial_anagram(a: &str, b: &str) -> bool {
-    let mut a_counts = to_counter(a);
-    let b_counts = to_counter(b);
-    return counter_contains(&a_counts, &b_counts);
+fn is_partial_anagram(a: &str, b: &str) -> Result<bool, AgarError> {
+    let a_counts = to_counter(a)?;
+    let b_counts = to_counter(b)?;
+    Ok(counter_contains(&a_counts, &b_counts))
 }
 
-const PRIMES: [u64; 26] = [
-    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
-    101,
-];
+/** Whether the letters of `smaller` are all available in `larger`. */
+#[cfg(feature = "grid")]
+fn contained(smaller: &str, larger: &str) -> bool {
+    is_partial_anagram(larger, smaller).unwrap_or(false)
+}
 
-#[derive(Debug, Default)]
-struct CounterNode {
-    product: WordProduct,
-    children: Vec<(i8, CounterNode)>,
+/**
+ * The distinct words of a dictionary, normalized and ranked by frequency, leaving out words with
+ * characters other than letters and spaces. Word ids are positions in this list, so `top_n`
+ * means the same thing to every solver.
+ */
+fn rank_words(dictionary: &dyn Dictionary) -> Vec<(String, u32)> {
+    layout::rank_words(dictionary.entries()).0
 }
 
 impl CounterNode {
-    fn new() -> Self {
-        Default::default()
-    }
-
-    fn insert(&mut self, counter: &Counter, counter_product: &WordProduct, index: usize) {
-        let remaining_sum = counter.iter().skip(index).sum::<i8>();
-
-        if remaining_sum == 0 {
-            if self.product != 0 {
-                panic!("Duplicate product");
-            }
-            self.product = *counter_product;
-            return;
-        }
-
-        let count = counter[index];
-
-        let mut exists = false;
-
-        for (j, child) in &mut self.children {
-            if *j == count {
-                child.insert(counter, counter_product, index + 1);
-                exists = true;
-                break;
+    /** Make room for a new key by incrementing every key at or after it. */
+    fn shift_keys(&mut self, from: WordKey) {
+        if let Some(key) = &mut self.key {
+            if *key >= from {
+                *key += 1;
             }
         }
-
-        if !exists {
-            let mut new = CounterNode::new();
-            new.insert(counter, counter_product, index + 1);
-            self.children.push((count, new));
-        }
-    }
-
-    fn sort(&mut self) {
-        self.children.sort_by(|a, b| a.0.cmp(&b.0));
         for (_, child) in &mut self.children {
-            child.sort();
+            child.shift_keys(from);
         }
     }
 
@@ -176,15 +150,15 @@ impl CounterNode {
         &self,
         target_counter: &Counter,
         index: usize,
-        result_products: &mut Vec<WordProduct>,
+        result_keys: &mut Vec<WordKey>,
     ) {
-        if self.product != 0 {
-            result_products.push(self.product);
+        if let Some(key) = self.key {
+            result_keys.push(key);
         }
 
         for (child_count, child) in &self.children {
             if *child_count <= target_counter[index] {
-                child.retrieve_anagrams(target_counter, index + 1, result_products);
+                child.retrieve_anagrams(target_counter, index + 1, result_keys);
             } else {
                 // we can stop here because the children are sorted
                 break;
@@ -193,267 +167,781 @@ impl CounterNode {
     }
 }
 
-// recursively find anagrams given a target product
-fn find_anagrams_counter(
-    target_length: usize,
-    target_counter: &mut Counter,
-    product_to_length: &HashMap<WordProduct, usize, BuildHasherDefault<FxHasher>>,
-    min_word_length: &usize,
-    max_num_words: &usize,
-    path: &mut Vec<WordProduct>,
-    found_anagrams: &mut Vec<Vec<WordProduct>>,
-    product_to_counter: &HashMap<WordProduct, Counter, BuildHasherDefault<FxHasher>>,
-    counter_root: &CounterNode,
-    min_product: WordProduct,
-    cache: &mut HashMap<Counter, Vec<WordProduct>, BuildHasherDefault<FxHasher>>,
-) {
-    let mut products = Vec::new();
-
-    match cache.get(target_counter) {
-        None => {
-            counter_root.retrieve_anagrams(target_counter, 0, &mut products);
-            glidesort::sort(&mut products);
-            products.reverse();
-            cache.insert(target_counter.clone(), products.clone());
+/** The preprocessed dictionary shared by a solver and the searches it starts. */
+#[derive(Clone)]
+struct Index {
+    // the words, one per line, and where each starts and ends in `text`, in dictionary order
+    // (most frequent first)
+    text: String,
+    spans: Vec<(usize, usize)>,
+    counts: Vec<u32>,
+    // words added at runtime come after the first `ranked` words and ignore `top_n`
+    ranked: usize,
+    removed: usize,
+    // the count added words get by default, taken when the index is built, since updating counts
+    // in place leaves them out of order
+    median_count: u32,
+    // a hash of the words and counts, and of every change since, which resume tokens are tied to
+    generation: u64,
+    // maps letters to their rank by frequency, so the counter tree branches on common letters first
+    index_map: [usize; ALPHA_SIZE],
+    // every distinct letter multiset gets its own key, so keys can never collide
+    counter_to_key: FxHashMap<Counter, WordKey>,
+    // indexed by key
+    key_to_words: Vec<Vec<usize>>,
+    key_to_length: Vec<usize>,
+    key_to_counter: Vec<Counter>,
+    root: CounterNode,
+}
+
+impl Index {
+    fn new(dictionary: &dyn Dictionary) -> Result<Self, AgarError> {
+        let word_counts = rank_words(dictionary);
+        let index_map = layout::rank_letters(word_counts.iter().map(|(word, _)| word.as_str()));
+        let counters = word_counts
+            .iter()
+            .map(|(word, _)| to_counter_indexed(word, &index_map))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (words, counts) = word_counts.into_iter().unzip();
+
+        Ok(Index::from_layout(Layout::new(
+            words, counts, index_map, &counters,
+        )))
+    }
+
+    fn from_layout(layout: Layout) -> Self {
+        let mut counter_to_key = FxHashMap::default();
+        counter_to_key.reserve(layout.counters.len());
+        for (key, counter) in layout.counters.iter().enumerate() {
+            counter_to_key.insert(*counter, key as WordKey);
         }
+        let key_to_length = layout.counters.iter().map(counter_length).collect();
+
+        let mut ranked_counts = layout.counts[..layout.ranked].to_vec();
+        let median_count = match ranked_counts.len() {
+            0 => 0,
+            len => {
+                *ranked_counts
+                    .select_nth_unstable_by(len / 2, |a, b| b.cmp(a))
+                    .1
+            }
+        };
 
-        Some(p) => {
-            products.extend(p);
+        let mut generation = Fingerprint::new();
+        generation.write_number(layout.ranked);
+        for id in 0..layout.len() {
+            generation.write(layout.word(id).as_bytes());
+            generation.write(b"\n");
+        }
+        for &count in &layout.counts {
+            generation.write(&count.to_le_bytes());
         }
-    };
 
-    for product in products {
-        // products are sorted in descending order, so we can stop if the product is too small
-        if product < min_product {
-            break;
+        Index {
+            // one string for all the words, rather than one allocation each; this only copies
+            // them if the layout borrowed them from an index the caller still holds
+            text: layout.text.into_owned(),
+            spans: layout.spans,
+            counts: layout.counts,
+            ranked: layout.ranked,
+            removed: 0,
+            median_count,
+            generation: generation.0,
+            index_map: layout.index_map,
+            counter_to_key,
+            key_to_words: layout.keys,
+            key_to_length,
+            key_to_counter: layout.counters,
+            root: layout.root,
         }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, AgarError> {
+        let layout = Layout::decode(bytes).map_err(|reason| AgarError::InvalidIndex { reason })?;
+        Ok(Index::from_layout(layout))
+    }
 
-        let product_counter = product_to_counter.get(&product).unwrap();
-        let product_length = product_to_length.get(&product).unwrap();
+    fn from_buffer(bytes: Vec<u8>) -> Result<Self, AgarError> {
+        let layout =
+            Layout::decode_owned(bytes).map_err(|reason| AgarError::InvalidIndex { reason })?;
+        Ok(Index::from_layout(layout))
+    }
 
-        if product_length < min_word_length {
-            continue;
+    fn to_bytes(&self) -> Vec<u8> {
+        // leave out removed words, which no longer belong to a key
+        let mut grouped = vec![false; self.num_words()];
+        for &id in self.key_to_words.iter().flatten() {
+            grouped[id] = true;
         }
+        let live = (0..self.num_words())
+            .filter(|&id| grouped[id])
+            .collect::<Vec<_>>();
 
-        let new_target_length = target_length - product_length;
-
-        if new_target_length == 0 {
-            path.push(product.clone());
-            found_anagrams.push(path.clone());
-            path.pop();
-        } else if new_target_length < *min_word_length || path.len() == *max_num_words - 1 {
-            continue;
-        } else {
-            path.push(product.clone());
-
-            subtract_counters(target_counter, product_counter);
-            find_anagrams_counter(
-                new_target_length,
-                target_counter,
-                product_to_length,
-                min_word_length,
-                max_num_words,
-                path,
-                found_anagrams,
-                product_to_counter,
-                counter_root,
-                product,
-                cache,
-            );
-            add_counters(target_counter, product_counter);
-            path.pop();
+        let mut new_ids = vec![0; self.num_words()];
+        for (new_id, &id) in live.iter().enumerate() {
+            new_ids[id] = new_id;
         }
+        let keys = self
+            .key_to_words
+            .iter()
+            .map(|ids| ids.iter().map(|&id| new_ids[id]).collect())
+            .collect::<Vec<_>>();
+
+        let counts = live.iter().map(|&id| self.counts[id]).collect::<Vec<_>>();
+        let ranked = live.partition_point(|&id| id < self.ranked);
+        layout::encode(
+            |new_id| self.word(live[new_id]),
+            &counts,
+            ranked,
+            &self.index_map,
+            &keys,
+            &self.key_to_counter,
+        )
     }
-}
 
-fn counter_solve(
-    target: &str,
-    min_length: usize,
-    max_num_words: usize,
-    excludes: &HashSet<String>,
-    includes: &Vec<String>,
-    top_n: usize,
-) -> (Vec<String>, Vec<String>) {
-    // filter out all non-abecedarian characters
-    let mut target = target
-        .chars()
-        .filter(|c| c.is_ascii_alphabetic())
-        .collect::<String>()
-        .to_lowercase();
-
-    for included in includes {
-        if !is_partial_anagram(&target, included) {
-            return (Vec::new(), Vec::new());
+    fn word(&self, id: usize) -> &str {
+        let (start, end) = self.spans[id];
+        &self.text[start..end]
+    }
+
+    /** The number of word ids, including removed words. */
+    fn num_words(&self) -> usize {
+        self.spans.len()
+    }
+
+    fn len(&self) -> usize {
+        self.num_words() - self.removed
+    }
+
+    /** Look up a word, returning `None` if it is not in the dictionary. */
+    fn word_id(&self, word: &str) -> Option<usize> {
+        self.locate(word).map(|(_, id)| id)
+    }
+
+    fn locate(&self, word: &str) -> Option<(WordKey, usize)> {
+        let counter = to_counter_indexed(word, &self.index_map).ok()?;
+        let key = *self.counter_to_key.get(&counter)?;
+
+        self.key_to_words[key as usize]
+            .iter()
+            .copied()
+            .find(|&id| self.word(id) == word)
+            .map(|id| (key, id))
+    }
+
+    fn add_word(&mut self, word: &str, count: Option<u32>) -> Result<bool, AgarError> {
+        // an empty word would fit in every seed, and turn up in every anagram
+        if word.is_empty() {
+            return Err(AgarError::EmptyWord);
+        }
+        if let Some(id) = self.word_id(word) {
+            if let Some(count) = count {
+                self.counts[id] = count;
+                self.record_change("add", word, count);
+            }
+            return Ok(false);
         }
 
-        for c in included.chars() {
-            target = target.replacen(&c.to_string(), "", 1);
+        let counter = to_counter_indexed(word, &self.index_map)?;
+        let count = count.unwrap_or(self.median_count);
+        let key = match self.counter_to_key.get(&counter) {
+            Some(&key) => key,
+            None => self.insert_key(counter),
+        };
+
+        let id = self.num_words();
+        self.key_to_words[key as usize].push(id);
+        self.spans
+            .push((self.text.len(), self.text.len() + word.len()));
+        self.text.push_str(word);
+        self.text.push('\n');
+        self.counts.push(count);
+        self.record_change("add", word, count);
+        Ok(true)
+    }
+
+    /** Give a new letter multiset a key in (length, counter) order, shifting the keys after it. */
+    fn insert_key(&mut self, counter: Counter) -> WordKey {
+        let length = counter_length(&counter);
+        let position = self
+            .key_to_counter
+            .partition_point(|other| (counter_length(other), other) < (length, &counter));
+        let key = position as WordKey;
+
+        for other in self.counter_to_key.values_mut() {
+            if *other >= key {
+                *other += 1;
+            }
         }
-    };
+        self.root.shift_keys(key);
+        self.root.insert(&counter, key, 0);
 
-    let dictionary = include_str!("dictionary_counts.txt");
+        self.counter_to_key.insert(counter, key);
 
-    let mut word_counts = Vec::new();
-    for line in dictionary.split("\n").take(top_n) {
-        if line.len() == 0 {
-            continue;
+        self.key_to_words.insert(position, Vec::new());
+        self.key_to_length.insert(position, length);
+        self.key_to_counter.insert(position, counter);
+        key
+    }
+
+    /** Fold a change into `generation`, so resume tokens from before it no longer match. */
+    fn record_change(&mut self, change: &str, word: &str, count: u32) {
+        let mut generation = Fingerprint(self.generation);
+        generation.write_str(change);
+        generation.write_str(word);
+        generation.write(&count.to_le_bytes());
+        self.generation = generation.0;
+    }
+
+    fn remove_word(&mut self, word: &str) -> bool {
+        match self.locate(word) {
+            Some((key, id)) => {
+                // the key stays in the tree; searches skip keys without words
+                self.key_to_words[key as usize].retain(|&other| other != id);
+                self.removed += 1;
+                self.record_change("remove", word, 0);
+                true
+            }
+            None => false,
         }
-        let mut split = line.split("\t");
-        let word = split.next().unwrap().to_lowercase();
-        let count = split.next().unwrap().parse::<u32>().unwrap();
-        word_counts.push((word.to_string(), count));
     }
+}
 
-    let filtered_lines = word_counts // using String as the return type of `to_lowercase`
-        .iter()
-        .filter(|(word, _)| filter_line(word, &target, min_length, excludes))
-        .map(|(word, _)| word.clone())
-        .collect::<Vec<String>>();
+// the index of the built-in dictionary, gzipped by build.rs
+static BUILTIN_INDEX: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dictionary.idx.gz"));
 
-    let counts_map = FxHashMap::from_iter(
-        word_counts
-            .iter()
-            .map(|(word, count)| (encode_word(word), count)),
-    );
+/** Inflate the built-in index. This only happens when a solver or word list needs it. */
+fn builtin_index() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    GzDecoder::new(BUILTIN_INDEX)
+        .read_to_end(&mut bytes)
+        .expect("built-in index is valid gzip");
+    bytes
+}
 
-    let mut letter_frequencies = [0; ALPHA_SIZE];
-    for line in &filtered_lines {
-        // lowercase
-        let line = line.to_lowercase();
-        for c in line.chars() {
-            let i = to_index(c);
-            letter_frequencies[i] += 1;
+/** How many bytes the built-in dictionary adds to the binary or wasm bundle. */
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DictionarySize {
+    // the gzipped index, which is what gets embedded
+    pub compressed: usize,
+    pub uncompressed: usize,
+    // the `word\tcount` list it was built from
+    pub word_list: usize,
+}
+
+impl fmt::Display for DictionarySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} KiB embedded ({} KiB inflated, from a {} KiB word list)",
+            self.compressed / 1024,
+            self.uncompressed / 1024,
+            self.word_list / 1024
+        )
+    }
+}
+
+#[wasm_bindgen]
+pub fn builtin_dictionary_size() -> DictionarySize {
+    DictionarySize {
+        compressed: BUILTIN_INDEX.len(),
+        uncompressed: env!("AGAR_MAN_INDEX_SIZE").parse().unwrap_or(0),
+        word_list: env!("AGAR_MAN_WORD_LIST_SIZE").parse().unwrap_or(0),
+    }
+}
+
+/** The parameters of a single anagram search. */
+#[derive(Clone, Debug)]
+pub struct Query {
+    pub seed: String,
+    pub min_length: usize,
+    pub max_num_words: usize,
+    pub excludes: HashSet<String>,
+    pub includes: Vec<String>,
+    // only use the `top_n` most common dictionary words
+    pub top_n: usize,
+    // ranks the anagrams found; `Mean` unless set
+    pub scorer: Arc<dyn Scorer>,
+    pub budget: Budget,
+    pub cancellation: CancellationToken,
+}
+
+impl Query {
+    pub fn new(seed: &str) -> Self {
+        Query {
+            seed: seed.to_string(),
+            min_length: 3,
+            max_num_words: 5,
+            excludes: HashSet::new(),
+            includes: Vec::new(),
+            top_n: usize::MAX,
+            scorer: Arc::new(Mean),
+            budget: Budget::default(),
+            cancellation: CancellationToken::new(),
         }
     }
+}
 
-    // sort primes by character frequency
-    // argsort letter_frequencies
-    let mut index_map: [usize; ALPHA_SIZE] = [
-        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
-        25,
-    ];
+/** A single anagram, with the frequency information used to rank it. */
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Anagram {
+    // included words first, then the words found by the search, in the order the scorer prefers
+    pub words: Vec<String>,
+    // the query's score for the words found by the search, the mean count by default
+    pub score: f32,
+    // log-frequency counts from the dictionary, one per word (0 for unknown included words)
+    pub per_word_counts: Vec<u32>,
+    pub letters_used: usize,
+}
 
-    // argsort target_counts
-    let mut sorted_indices: Vec<usize> = (0..ALPHA_SIZE).collect();
-    sorted_indices.sort_by(|a, b| letter_frequencies[*b].cmp(&letter_frequencies[*a]));
+impl Anagram {
+    /** The included words followed by the words found by a search, scored by the latter. */
+    fn new<'a>(
+        includes: &[String],
+        include_counts: &[u32],
+        found: impl Iterator<Item = (&'a str, u32)>,
+        scorer: &dyn Scorer,
+    ) -> Self {
+        let mut anagram = Anagram {
+            words: includes.to_vec(),
+            score: 0.0,
+            per_word_counts: include_counts.to_vec(),
+            letters_used: includes.iter().map(|word| letter_count(word)).sum(),
+        };
+
+        for (word, count) in found {
+            anagram.letters_used += letter_count(word);
+            anagram.words.push(word.to_string());
+            anagram.per_word_counts.push(count);
+        }
+        // only the words found by the search count towards the score
+        let num_included = includes.len();
+        if anagram.words.len() > num_included {
+            let words = &anagram.words[num_included..];
+            let counts = &anagram.per_word_counts[num_included..];
+            if let Some(order) = scorer.arrange(words, counts) {
+                let (words, counts) = order
+                    .iter()
+                    .map(|&i| (words[i].clone(), counts[i]))
+                    .unzip::<_, _, Vec<_>, Vec<_>>();
+                anagram.words.truncate(num_included);
+                anagram.words.extend(words);
+                anagram.per_word_counts.truncate(num_included);
+                anagram.per_word_counts.extend(counts);
+            }
+            anagram.score = scorer.score(
+                &anagram.words[num_included..],
+                &anagram.per_word_counts[num_included..],
+            );
+        }
 
-    // map indices to sorted indices
-    for (i, j) in sorted_indices.iter().enumerate() {
-        index_map[*j] = i;
+        anagram
     }
+}
 
-    // assign primes to letters
-    let mut letter_primes = [0; ALPHA_SIZE];
-    for (i, j) in sorted_indices.iter().enumerate() {
-        letter_primes[*j] = PRIMES[i];
+impl fmt::Display for Anagram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.words.join(" "))
     }
+}
+
+/** The sorted anagrams of a query, along with every word that fits in its seed. */
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Solution {
+    pub anagrams: Vec<Anagram>,
+    pub partials: Vec<String>,
+    // set if the search stopped before finding every anagram
+    pub truncated: Option<Truncation>,
+}
 
-    // hashmap of products to words
-    let mut product_to_words: HashMap<WordProduct, Vec<EncodedWord>, BuildHasherDefault<FxHasher>> =
-        FxHashMap::default();
-    let mut product_to_length = FxHashMap::default();
-    let mut product_to_counter = FxHashMap::default();
+/** How many anagrams a query has, counted without finding them. */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnagramCount {
+    // anagrams as `solve` would find them
+    pub anagrams: BigUint,
+    // the same, but counting words with the same letters (like "post" and "stop") only once
+    pub letter_combinations: BigUint,
+    // set if the count stopped early, in which case both are lower bounds
+    pub truncated: Option<Truncation>,
+}
 
-    let mut root = CounterNode::new();
+/** How a proposed anagram compares with its seed. */
+#[derive(Clone, Debug, Serialize)]
+pub struct Verification {
+    // whether the words use exactly the letters of the seed
+    pub is_anagram: bool,
+    // letters of the seed the words leave unused, and letters they need beyond it, sorted
+    pub unused: String,
+    pub extra: String,
+    // words that are not in the dictionary, which count as 0 towards the score
+    pub unknown_words: Vec<String>,
+    pub anagram: Anagram,
+}
+
+/** A dictionary preprocessed once, so that many queries can be answered against it. */
+#[derive(Clone)]
+pub struct AnagramSolver {
+    index: Arc<Index>,
+}
 
-    for line in &filtered_lines {
-        // lowercase
-        let line = line.to_lowercase();
-        let mut product: WordProduct = 1;
-        let length = line.len();
-        for c in line.chars() {
-            let i = to_index(c);
-            product *= letter_primes[i] as WordProduct;
+impl Default for AnagramSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnagramSolver {
+    /** Build a solver over the built-in dictionary, whose index is precomputed by build.rs. */
+    pub fn new() -> Self {
+        let index = Index::from_buffer(builtin_index()).expect("built-in index is valid");
+        AnagramSolver {
+            index: Arc::new(index),
         }
+    }
 
-        let product_counter = to_counter_indexed(&line, &index_map);
+    /** Load an index saved with `to_index`, which is much faster than building one. */
+    pub fn from_index(bytes: &[u8]) -> Result<Self, AgarError> {
+        Ok(AnagramSolver {
+            index: Arc::new(Index::from_bytes(bytes)?),
+        })
+    }
 
-        // products_by_length[length].insert(product);
-        product_to_length.insert(product, length);
-        product_to_counter.entry(product).or_insert(product_counter);
+    /** Save the preprocessed dictionary, including words added or removed at runtime. */
+    pub fn to_index(&self) -> Vec<u8> {
+        self.index.to_bytes()
+    }
 
-        if let Some(words) = product_to_words.get_mut(&product) {
-            words.push(encode_word(&line));
-        } else {
-            product_to_words.insert(product, vec![encode_word(&line)]);
-        }
+    /**
+     * Build a solver over any word list, e.g. one loaded with `WordList::from_path`. Words with
+     * characters other than letters and spaces, like "don't", are left out.
+     */
+    pub fn from_dictionary(dictionary: &dyn Dictionary) -> Result<Self, AgarError> {
+        Ok(AnagramSolver {
+            index: Arc::new(Index::new(dictionary)?),
+        })
     }
 
-    for (product, counter) in &product_to_counter {
-        root.insert(counter, product, 0);
+    /** Number of words in the dictionary. */
+    pub fn len(&self) -> usize {
+        self.index.len()
     }
-    root.sort();
 
-    let mut target_counter = to_counter_indexed(&target, &index_map);
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-    let mut found_anagrams = Vec::new();
-    let mut cache = FxHashMap::default();
+    /**
+     * Add a word on top of the dictionary, such as a name or a brand. Added words are always
+     * used regardless of `top_n`, and without a count they get the median dictionary count.
+     * Returns false if the word was already present, in which case only its count is updated, and
+     * fails if the word has no letters. Searches that are already running keep the dictionary
+     * they started with.
+     */
+    pub fn add_word(&mut self, word: &str, count: Option<u32>) -> Result<bool, AgarError> {
+        let word = normalize_word(word);
+        check_word(&word)?;
+        Arc::make_mut(&mut self.index).add_word(&word, count)
+    }
 
-    find_anagrams_counter(
-        target.len(),
-        &mut target_counter,
-        &product_to_length,
-        &min_length,
-        &(max_num_words - includes.len()),
-        &mut Vec::with_capacity(target.len()),
-        &mut found_anagrams,
-        &product_to_counter,
-        &root,
-        2,
-        &mut cache,
-    );
+    /** Remove a word from the dictionary, returning false if it was not present. */
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let word = normalize_word(word);
+        self.index.word_id(&word).is_some() && Arc::make_mut(&mut self.index).remove_word(&word)
+    }
 
-    // convert to strings, expanding each product to all possible words
-    let mut found_anagrams_strings = Vec::new();
+    /** Lazily enumerate the anagrams of a query, in search order. */
+    pub fn anagrams(&self, query: &Query) -> Result<Anagrams, AgarError> {
+        Anagrams::new(self.index.clone(), query)
+    }
 
-    for anagram in &found_anagrams {
-        let mut anagram_strings = Vec::new();
+    /** Find the anagrams of a query within its budget, sorted by the query's scorer. */
+    pub fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        let mut anagrams = self.anagrams(query)?;
+        let partials = anagrams.partials();
 
-        for product in anagram {
-            let words = product_to_words.get(product).unwrap();
-            anagram_strings.push(words.clone());
-        }
-        // take the cartesian product of the words
-        let expanded = anagram_strings.iter().multi_cartesian_product();
+        let mut found_anagrams = anagrams.by_ref().collect::<Vec<_>>();
+        glidesort::sort_by(&mut found_anagrams, |a, b| b.score.total_cmp(&a.score));
 
-        for product in expanded {
-            let mut string = String::new();
+        Ok(Solution {
+            anagrams: found_anagrams,
+            partials,
+            truncated: anagrams.truncated(),
+        })
+    }
 
-            for included in includes {
-                string.push_str(included);
-                string.push(' ');
-            }
+    /**
+     * Count the anagrams of a query without enumerating them, by memoising the number of ways to
+     * complete each remainder of the seed. This takes time in the number of distinct remainders
+     * rather than the number of anagrams. `max_results` is ignored; the rest of the budget applies.
+     */
+    pub fn count(&self, query: &Query) -> Result<AnagramCount, AgarError> {
+        Ok(self.anagrams(query)?.count_anagrams())
+    }
+
+    /**
+     * Draw `num_samples` random anagrams of a query, with replacement, in the order drawn. The
+     * anagrams come from all of the query's anagrams, as weighted by `sampling`, and the same
+     * `seed` always draws the same ones from the same dictionary. Drawing relies on counting the
+     * anagrams first, so nothing is drawn if the budget runs out before then.
+     */
+    pub fn sample(
+        &self,
+        query: &Query,
+        num_samples: usize,
+        sampling: Sampling,
+        seed: u64,
+    ) -> Result<Solution, AgarError> {
+        let mut anagrams = self.anagrams(query)?;
+        let partials = anagrams.partials();
+        let found_anagrams = anagrams.sample(num_samples, sampling, &mut sample::Rng::new(seed));
+
+        Ok(Solution {
+            anagrams: found_anagrams,
+            partials,
+            truncated: anagrams.truncated(),
+        })
+    }
+
+    /**
+     * Pick up a search where another left off, given the `resume_token` it returned for the same
+     * query. The budget is fresh, so this is how to page through a search: set `max_results` to
+     * the page size, and resume from each page's token for the next one.
+     */
+    pub fn resume(&self, query: &Query, token: &str) -> Result<Anagrams, AgarError> {
+        let mut anagrams = self.anagrams(query)?;
+        anagrams.resume(token)?;
+        Ok(anagrams)
+    }
 
-            let mut count_avg = 0.0;
-            let mut num_words = 0;
-            for word in product {
-                let word_count = counts_map.get(word).unwrap();
-                decode_and_extend_word(word, &mut string);
-                count_avg += **word_count as f32;
-                num_words += 1;
+    /**
+     * The `k` highest-scoring anagrams of a query, best first, without enumerating the rest. This
+     * finds the same scores as the first `k` anagrams of `solve`, but ties are always broken in
+     * favour of more common words, where `solve` keeps them in search order. `max_results` is
+     * ignored, since `k` already bounds the results. Only scorers with a `bound` prune the
+     * search; the others still enumerate every anagram, and keep ties in search order.
+     */
+    pub fn solve_best(&self, query: &Query, k: usize) -> Result<Solution, AgarError> {
+        let mut anagrams = self.anagrams(query)?;
+        let partials = anagrams.partials();
+        let found_anagrams = anagrams.best(k);
+
+        Ok(Solution {
+            anagrams: found_anagrams,
+            partials,
+            truncated: anagrams.truncated(),
+        })
+    }
+
+    /**
+     * The anagrams from `offset` to `offset + limit` in the order of `solve_best`, which is the
+     * same from one call to the next, so pages never overlap or skip an anagram. Each page is
+     * recomputed from scratch: this runs `solve_best` for `offset + limit` anagrams and drops the
+     * first `offset`, so paging through `n` anagrams takes O(n²) work in all. To walk through
+     * every anagram in search order instead, page with `resume`, which continues where the last
+     * page stopped.
+     */
+    pub fn solve_page(
+        &self,
+        query: &Query,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Solution, AgarError> {
+        let mut solution = self.solve_best(query, offset.saturating_add(limit))?;
+        solution
+            .anagrams
+            .drain(..offset.min(solution.anagrams.len()));
+        Ok(solution)
+    }
+
+    /**
+     * Like `solve`, but searching the branches under each first word on separate threads. The
+     * results are merged in search order, so they match `solve` exactly unless the deadline,
+     * `max_nodes` or cancellation (which the threads share) cut the search short.
+     */
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn solve_parallel(&self, query: &Query) -> Result<Solution, AgarError> {
+        use rayon::prelude::*;
+        use std::sync::atomic::AtomicU64;
+
+        let anagrams = self.anagrams(query)?;
+        let partials = anagrams.partials();
+        let stopwatch = budget::Stopwatch::start();
+        let shared_nodes = query.budget.max_nodes.map(|_| Arc::new(AtomicU64::new(0)));
+
+        // the words that fit are found once; each worker only keeps its own cursor and cache
+        let search = anagrams.search();
+        let branches = anagrams
+            .first_keys()
+            .into_par_iter()
+            .map_init(
+                || {
+                    let mut anagrams = Anagrams::with_search(search.clone(), query);
+                    anagrams.share_budget(stopwatch, shared_nodes.clone());
+                    anagrams
+                },
+                |anagrams, key| {
+                    anagrams.start_branch(key);
+                    let found = anagrams.by_ref().collect::<Vec<_>>();
+                    (found, anagrams.truncated())
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let mut found_anagrams = Vec::new();
+        let mut truncated = None;
+        for (found, reason) in branches {
+            found_anagrams.extend(found);
+            truncated = truncated.or(reason);
+        }
+        // every branch stops at `max_results`, so keep the ones a sequential search would find
+        if let Some(max_results) = query.budget.max_results {
+            if found_anagrams.len() > max_results {
+                found_anagrams.truncate(max_results);
+                truncated = Some(Truncation::MaxResults);
             }
-            count_avg /= num_words as f32;
+        }
+        glidesort::sort_by(&mut found_anagrams, |a, b| b.score.total_cmp(&a.score));
+
+        Ok(Solution {
+            anagrams: found_anagrams,
+            partials,
+            truncated,
+        })
+    }
 
-            string.pop();
-            found_anagrams_strings.push((string, count_avg));
+    /** Check whether `words` are an anagram of `seed`, scoring them by their mean count. */
+    pub fn verify(&self, seed: &str, words: &[String]) -> Result<Verification, AgarError> {
+        let words = words
+            .iter()
+            .map(|word| normalize_word(word))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+
+        // positive for letters left over, negative for letters missing from the seed
+        let mut difference = [0i32; ALPHA_SIZE];
+        for c in seed.chars().filter(|c| c.is_ascii_alphabetic()) {
+            difference[to_index(c.to_ascii_lowercase())] += 1;
         }
+        for word in &words {
+            check_word(word)?;
+            for c in word.chars().filter(|&c| c != ' ') {
+                difference[to_index(c)] -= 1;
+            }
+        }
+
+        let counts = words
+            .iter()
+            .map(|word| self.index.word_id(word).map(|id| self.index.counts[id]))
+            .collect::<Vec<_>>();
+
+        Ok(Verification {
+            is_anagram: difference.iter().all(|&count| count == 0),
+            unused: letters_of(difference.iter().copied()),
+            extra: letters_of(difference.iter().map(|&count| -count)),
+            unknown_words: words
+                .iter()
+                .zip(&counts)
+                .filter(|(_, count)| count.is_none())
+                .map(|(word, _)| word.clone())
+                .collect(),
+            anagram: Anagram::new(
+                &[],
+                &[],
+                words
+                    .iter()
+                    .zip(&counts)
+                    .map(|(word, count)| (word.as_str(), count.unwrap_or(0))),
+                &Mean,
+            ),
+        })
     }
+}
 
-    glidesort::sort_by(&mut found_anagrams_strings, |a, b| b.1.total_cmp(&a.1));
+/** Spell out the letters of a count difference, each letter as many times as it occurs. */
+fn letters_of(counts: impl Iterator<Item = i32>) -> String {
+    counts
+        .enumerate()
+        .flat_map(|(i, count)| std::iter::repeat_n((b'a' + i as u8) as char, count.max(0) as usize))
+        .collect()
+}
 
-    let found_anagrams_strings = found_anagrams_strings
-        .into_iter()
-        .map(|(string, _)| string)
-        .collect::<Vec<String>>();
+/** Find anagrams of `target` against the built-in dictionary, preprocessed once per thread. */
+pub fn counter_solve(
+    target: &str,
+    min_length: usize,
+    max_num_words: usize,
+    excludes: &HashSet<String>,
+    includes: &[String],
+    top_n: usize,
+) -> Result<(Vec<String>, Vec<String>), AgarError> {
+    let query = Query {
+        min_length,
+        max_num_words,
+        excludes: excludes.clone(),
+        includes: includes.to_vec(),
+        top_n,
+        ..Query::new(target)
+    };
+    let solution = SOLVER.with(|solver| solver.solve(&query))?;
+    let anagrams = solution.anagrams.iter().map(Anagram::to_string).collect();
+    Ok((anagrams, solution.partials))
+}
 
-    return (found_anagrams_strings, filtered_lines);
+thread_local! {
+    // shared by `js_generate` and `counter_solve`, so the dictionary is only preprocessed once per
+    // page load or thread
+    static SOLVER: AnagramSolver = AnagramSolver::new();
 }
 
 #[wasm_bindgen(getter_with_clone)]
 pub struct ResultsStruct {
-    // pub value: String, // This won't work. See working example below.
-    pub anagrams: js_sys::Array,
-    pub partials: js_sys::Array,
+    pub anagrams: Vec<Anagram>,
+    pub partials: Vec<String>,
+    // why the search stopped early, if it did
+    pub truncated: Option<String>,
+}
+
+/** Split a comma-separated list of words, as typed in the search form. */
+fn parse_word_list(words: &str) -> impl Iterator<Item = String> + '_ {
+    words
+        .trim()
+        .split(',')
+        .map(normalize_word)
+        .filter(|x| !x.is_empty())
+}
+
+fn to_query(
+    seed: &str,
+    min_length: usize,
+    max_num_words: usize,
+    excludes: &str,
+    includes: &str,
+    top_n: usize,
+) -> Query {
+    Query {
+        min_length,
+        max_num_words,
+        excludes: parse_word_list(excludes).collect(),
+        includes: parse_word_list(includes).collect(),
+        top_n,
+        ..Query::new(seed)
+    }
+}
+
+impl From<Solution> for ResultsStruct {
+    fn from(solution: Solution) -> Self {
+        ResultsStruct {
+            anagrams: solution.anagrams,
+            partials: solution.partials,
+            truncated: solution.truncated.map(|reason| reason.to_string()),
+        }
+    }
+}
+
+fn generate_with(solver: &AnagramSolver, query: &Query) -> Result<ResultsStruct, AgarError> {
+    Ok(solver.solve(query)?.into())
 }
 
 #[wasm_bindgen]
@@ -464,22 +952,9 @@ pub fn js_generate(
     excludes: String,
     includes: String,
     top_n: usize,
-) -> ResultsStruct {
+) -> Result<ResultsStruct, AgarError> {
     console_error_panic_hook::set_once();
-    let excludes = excludes
-        .trim()
-        .split(",")
-        .map(|x| x.trim().to_lowercase())
-        .filter(|x| !x.is_empty())
-        .collect::<HashSet<_>>();
-    let includes = includes
-        .trim()
-        .split(",")
-        .map(|x| x.trim().to_lowercase())
-        .filter(|x| !x.is_empty())
-        .collect::<Vec<_>>();
-
-    let (anagrams, partials) = counter_solve(
+    let query = to_query(
         &seed,
         min_length,
         max_num_words,
@@ -487,45 +962,458 @@ pub fn js_generate(
         &includes,
         top_n,
     );
+    SOLVER.with(|solver| generate_with(solver, &query))
+}
+
+/** JS mirror of `Query`; word lists are comma-separated, as typed in the search form. */
+#[wasm_bindgen(js_name = Query, getter_with_clone)]
+pub struct JsQuery {
+    pub seed: String,
+    pub min_length: usize,
+    pub max_num_words: usize,
+    pub excludes: String,
+    pub includes: String,
+    pub top_n: usize,
+    // a scorer for `parse_scorer`, such as "mean" or "mean+100*fewer-words"
+    pub scorer: String,
+    // if set, rank by bigram likelihood instead of `scorer`
+    pub bigrams: Option<JsBigrams>,
+    pub max_results: Option<u32>,
+    // JS numbers are doubles, so node counts are too
+    pub max_nodes: Option<f64>,
+    pub timeout_ms: Option<f64>,
+}
 
-    let anagrams_js = Array::new_with_length(anagrams.len() as u32);
-    for i in 0..anagrams_js.length() {
-        let s = JsValue::from_str(anagrams[i as usize].as_str());
-        anagrams_js.set(i, s);
+#[wasm_bindgen(js_class = Query)]
+impl JsQuery {
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: String) -> Self {
+        let defaults = Query::new(&seed);
+        JsQuery {
+            seed,
+            min_length: defaults.min_length,
+            max_num_words: defaults.max_num_words,
+            excludes: String::new(),
+            includes: String::new(),
+            top_n: defaults.top_n,
+            scorer: "mean".to_string(),
+            bigrams: None,
+            max_results: None,
+            max_nodes: None,
+            timeout_ms: None,
+        }
     }
+}
 
-    let partials_js = Array::new_with_length(partials.len() as u32);
-    for i in 0..partials_js.length() {
-        let s = JsValue::from_str(partials[i as usize].as_str());
-        partials_js.set(i, s);
+impl JsQuery {
+    fn to_query(&self) -> Result<Query, AgarError> {
+        let mut query = to_query(
+            &self.seed,
+            self.min_length,
+            self.max_num_words,
+            &self.excludes,
+            &self.includes,
+            self.top_n,
+        );
+        query.budget = Budget {
+            max_results: self.max_results.map(|max| max as usize),
+            max_nodes: self.max_nodes.map(|max| max as u64),
+            deadline: self
+                .timeout_ms
+                .map(|ms| Duration::from_secs_f64(ms.max(0.0) / 1000.0)),
+        };
+        query.scorer = match &self.bigrams {
+            Some(bigrams) => Arc::new(BigramScorer(bigrams.bigrams.clone())),
+            None => parse_scorer(&self.scorer)?,
+        };
+        Ok(query)
     }
+}
 
-    return ResultsStruct {
-        anagrams: anagrams_js,
-        partials: partials_js,
-    };
+/** JS handle to a `Bigrams` table, shared by every `Query` it is set on. */
+#[wasm_bindgen(js_name = Bigrams)]
+#[derive(Clone)]
+pub struct JsBigrams {
+    bigrams: Arc<Bigrams>,
 }
 
-#[allow(dead_code)]
-fn main() {
-    // aggregate_1grams();
-    // filter_1grams();
-    // assign_counts();
+#[wasm_bindgen(js_class = Bigrams)]
+impl JsBigrams {
+    /** A table from `first\tsecond\tcount` lines, e.g. the text of a fetched file. */
+    pub fn from_tsv(text: &str) -> Result<JsBigrams, AgarError> {
+        Ok(JsBigrams {
+            bigrams: Arc::new(Bigrams::from_tsv(text)?),
+        })
+    }
+}
 
-    let target = "village technologies";
-    let min_length = 2;
-    let max_words = 10;
+/** JS handle to an `AnagramSolver`; construct it once and call `generate` for every `Query`. */
+#[wasm_bindgen(js_name = AnagramSolver)]
+pub struct JsAnagramSolver {
+    solver: AnagramSolver,
+}
 
-    let start = Instant::now();
-    let results = counter_solve(
-        target,
-        min_length,
-        max_words,
-        &HashSet::default(),
-        &vec!["the".to_string(), "ai".to_string()],
-        200_000,
-    );
-    println!("Anagrams: {:?}", results.0.len());
-    let duration = start.elapsed();
-    println!("Time elapsed: {:?}", duration);
+#[wasm_bindgen(js_class = AnagramSolver)]
+impl JsAnagramSolver {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        console_error_panic_hook::set_once();
+        JsAnagramSolver {
+            solver: AnagramSolver::new(),
+        }
+    }
+
+    /** Solver over a list of words, ranked in the order given. */
+    pub fn from_words(words: Vec<String>) -> Result<JsAnagramSolver, AgarError> {
+        let list = words.iter().map(|word| (word, 0)).collect::<WordList>();
+        JsAnagramSolver::from_word_list(&list)
+    }
+
+    /** Solver over a `Map` from words to frequency counts. */
+    pub fn from_map(map: &js_sys::Map) -> Result<JsAnagramSolver, AgarError> {
+        let mut list = WordList::new();
+        map.for_each(&mut |count, word| {
+            if let Some(word) = word.as_string() {
+                list.push(&word, count.as_f64().unwrap_or(0.0) as u32);
+            }
+        });
+        JsAnagramSolver::from_word_list(&list)
+    }
+
+    /** Solver over `word\tcount` lines, e.g. the text of a fetched file. */
+    pub fn from_tsv(text: &str) -> Result<JsAnagramSolver, AgarError> {
+        JsAnagramSolver::from_word_list(&WordList::from_tsv(text)?)
+    }
+
+    /** Solver over an index saved with `to_index`. */
+    pub fn from_index(bytes: &[u8]) -> Result<JsAnagramSolver, AgarError> {
+        console_error_panic_hook::set_once();
+        Ok(JsAnagramSolver {
+            solver: AnagramSolver::from_index(bytes)?,
+        })
+    }
+
+    pub fn to_index(&self) -> Vec<u8> {
+        self.solver.to_index()
+    }
+
+    /** Add a name, brand or other word missing from the dictionary; see `add_word`. */
+    pub fn add_word(&mut self, word: &str, count: Option<u32>) -> Result<bool, AgarError> {
+        self.solver.add_word(word, count)
+    }
+
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        self.solver.remove_word(word)
+    }
+
+    pub fn generate(&self, query: &JsQuery) -> Result<ResultsStruct, AgarError> {
+        generate_with(&self.solver, &query.to_query()?)
+    }
+
+    /** Only the `k` best anagrams of a query, without enumerating the rest; see `solve_best`. */
+    pub fn generate_best(&self, query: &JsQuery, k: usize) -> Result<ResultsStruct, AgarError> {
+        Ok(self.solver.solve_best(&query.to_query()?, k)?.into())
+    }
+
+    /** Draw random anagrams, favouring common words if `weighted`; seeds must be below 2^53. */
+    pub fn sample(
+        &self,
+        query: &JsQuery,
+        num_samples: usize,
+        weighted: bool,
+        seed: f64,
+    ) -> Result<ResultsStruct, AgarError> {
+        let sampling = if weighted {
+            Sampling::Frequency
+        } else {
+            Sampling::Uniform
+        };
+        Ok(self
+            .solver
+            .sample(&query.to_query()?, num_samples, sampling, seed as u64)?
+            .into())
+    }
+
+    /** The anagrams from `offset` to `offset + limit` by score; see `AnagramSolver::solve_page`. */
+    pub fn page(
+        &self,
+        query: &JsQuery,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ResultsStruct, AgarError> {
+        Ok(self
+            .solver
+            .solve_page(&query.to_query()?, offset, limit)?
+            .into())
+    }
+
+    /** Start a lazy search; pull results with `next_batch` instead of waiting for all of them. */
+    pub fn stream(&self, query: &JsQuery) -> Result<JsAnagramStream, AgarError> {
+        Ok(JsAnagramStream {
+            anagrams: self.solver.anagrams(&query.to_query()?)?,
+        })
+    }
+
+    /** Carry on from a stream's `resume_token`, e.g. to load more without keeping the stream. */
+    pub fn resume(&self, query: &JsQuery, token: &str) -> Result<JsAnagramStream, AgarError> {
+        Ok(JsAnagramStream {
+            anagrams: self.solver.resume(&query.to_query()?, token)?,
+        })
+    }
+}
+
+impl JsAnagramSolver {
+    fn from_word_list(list: &WordList) -> Result<JsAnagramSolver, AgarError> {
+        console_error_panic_hook::set_once();
+        Ok(JsAnagramSolver {
+            solver: AnagramSolver::from_dictionary(list)?,
+        })
+    }
+}
+
+impl Default for JsAnagramSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/** JS handle to an in-progress search, returned by `AnagramSolver.stream`. */
+#[wasm_bindgen(js_name = AnagramStream)]
+pub struct JsAnagramStream {
+    anagrams: Anagrams,
+}
+
+#[wasm_bindgen(js_class = AnagramStream)]
+impl JsAnagramStream {
+    /** Up to `n` more anagrams, in search order; an empty array means the search is done. */
+    pub fn next_batch(&mut self, n: usize) -> Vec<Anagram> {
+        self.anagrams.by_ref().take(n).collect()
+    }
+
+    /** Every word that fits in the seed. */
+    pub fn partials(&self) -> Vec<String> {
+        self.anagrams.partials()
+    }
+
+    /** Stop the search; later batches come back empty. */
+    pub fn cancel(&self) {
+        self.anagrams.cancellation_token().cancel();
+    }
+
+    /** Why the search stopped early, if it did. */
+    pub fn truncated(&self) -> Option<String> {
+        self.anagrams.truncated().map(|reason| reason.to_string())
+    }
+
+    /** A token for `AnagramSolver.resume`, or undefined once the search is done. */
+    pub fn resume_token(&self) -> Option<String> {
+        self.anagrams.resume_token()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // words made of the letters of "stop a rat", so its searches are small but branch a lot
+    const WORDS: &str = "a i o at to so it or as is sat its sit tis art rat tar oat tor rot pit \
+                         tip top pot opt apt pat tap star rats tsar arts stop post pots tops spot \
+                         opts sort rots taro part trap rapt tarp past spat taps pats roast parts \
+                         strap traps sprat sport strop";
+
+    /** A solver over `WORDS`, ranked in that order. */
+    pub(crate) fn test_solver() -> AnagramSolver {
+        test_solver_with(|rank| 1000 - rank as u32)
+    }
+
+    /** A solver over `WORDS`, counting the word at each rank with `count`. */
+    pub(crate) fn test_solver_with(count: impl Fn(usize) -> u32) -> AnagramSolver {
+        AnagramSolver::from_dictionary(&test_dictionary_with(count)).unwrap()
+    }
+
+    /** `WORDS`, counting the word at each rank with `count`, for engines other than the solver. */
+    pub(crate) fn test_dictionary_with(count: impl Fn(usize) -> u32) -> WordList {
+        WORDS
+            .split_whitespace()
+            .enumerate()
+            .map(|(rank, word)| (word, count(rank)))
+            .collect()
+    }
+
+    fn index(words: &[&str]) -> Index {
+        let mut dictionary = WordList::new();
+        for (rank, word) in words.iter().enumerate() {
+            dictionary.push(word, (words.len() - rank) as u32);
+        }
+        Index::new(&dictionary).unwrap()
+    }
+
+    /** Every key has its own letter multiset, and every word is under the key of its letters. */
+    fn assert_unique_keys(index: &Index) {
+        let mut counters = index.key_to_counter.clone();
+        counters.sort_unstable();
+        counters.dedup();
+        assert_eq!(counters.len(), index.key_to_counter.len());
+        assert_eq!(index.counter_to_key.len(), index.key_to_counter.len());
+
+        for (key, ids) in index.key_to_words.iter().enumerate() {
+            let counter = index.key_to_counter[key];
+            assert_eq!(index.counter_to_key[&counter], key as WordKey);
+            assert_eq!(index.key_to_length[key], counter_length(&counter));
+            let mut contained = Vec::new();
+            index.root.retrieve_anagrams(&counter, 0, &mut contained);
+            assert!(contained.contains(&(key as WordKey)));
+            for &id in ids {
+                let word = index.word(id);
+                assert_eq!(to_counter_indexed(word, &index.index_map).unwrap(), counter);
+            }
+        }
+    }
+
+    #[test]
+    fn words_with_the_same_letters_share_a_key() {
+        let index = index(&[
+            "stop", "post", "pots", "tops", "rat", "tar", "art", "star", "a",
+        ]);
+        assert_unique_keys(&index);
+        assert_eq!(index.key_to_words.len(), 4);
+
+        let (stop, _) = index.locate("stop").unwrap();
+        let (tops, _) = index.locate("tops").unwrap();
+        let (star, _) = index.locate("star").unwrap();
+        assert_eq!(stop, tops);
+        assert_ne!(stop, star);
+        // keys are numbered by length, so the longest words come last
+        assert!(index.locate("a").unwrap().0 < index.locate("rat").unwrap().0);
+        assert!(index.locate("rat").unwrap().0 < stop);
+    }
+
+    #[test]
+    fn added_words_keep_keys_unique() {
+        let mut index = index(&["stop", "rat", "tar", "a"]);
+        assert!(index.add_word("spot", None).unwrap());
+        assert!(index.add_word("at", Some(3)).unwrap());
+        assert!(index.add_word("zebra", None).unwrap());
+        assert!(!index.add_word("rat", Some(7)).unwrap());
+        assert_unique_keys(&index);
+        assert_eq!(index.key_to_words.len(), 5);
+        assert_eq!(index.counts[index.word_id("rat").unwrap()], 7);
+        assert_eq!(
+            index.locate("spot").unwrap().0,
+            index.locate("stop").unwrap().0
+        );
+
+        assert!(index.remove_word("tar"));
+        assert!(!index.remove_word("tar"));
+        assert_eq!(index.word_id("tar"), None);
+        assert_eq!(index.len(), 6);
+        assert_unique_keys(&index);
+    }
+
+    #[test]
+    fn added_words_get_the_median_count() {
+        // counted 4, 3, 2 and 1
+        let mut index = index(&["stop", "rat", "tar", "a"]);
+        assert!(index.add_word("spot", None).unwrap());
+        assert_eq!(index.counts[index.word_id("spot").unwrap()], 2);
+        // updating a count in place leaves the counts out of order, but not the median
+        assert!(!index.add_word("tar", Some(100)).unwrap());
+        assert!(index.add_word("zebra", None).unwrap());
+        assert_eq!(index.counts[index.word_id("zebra").unwrap()], 2);
+    }
+
+    #[test]
+    fn invalid_entries_are_skipped() {
+        let dictionary = [("stop", 9), ("don't", 8), ("café", 7), ("rat", 6)]
+            .into_iter()
+            .collect::<WordList>();
+        let solver = AnagramSolver::from_dictionary(&dictionary).unwrap();
+        assert_eq!(solver.len(), 2);
+        assert_eq!(solver.index.word_id("rat"), Some(1));
+        let mut query = Query::new("rat stop");
+        query.min_length = 1;
+        assert_eq!(solver.solve(&query).unwrap().anagrams.len(), 1);
+    }
+
+    #[test]
+    fn empty_words_are_rejected() {
+        let mut index = index(&["stop", "rat", "a"]);
+        assert_eq!(index.add_word("", None), Err(AgarError::EmptyWord));
+        assert_eq!(index.len(), 3);
+
+        let mut solver = AnagramSolver::from_dictionary(
+            &[("stop", 9), ("rat", 7), ("a", 5)]
+                .into_iter()
+                .collect::<WordList>(),
+        )
+        .unwrap();
+        assert_eq!(solver.add_word("  \t ", Some(5)), Err(AgarError::EmptyWord));
+        let mut query = Query::new("a rat stop");
+        query.min_length = 1;
+        let solution = solver.solve(&query).unwrap();
+        assert!(!solution.partials.iter().any(String::is_empty));
+        assert_eq!(
+            solver.count(&query).unwrap().anagrams,
+            BigUint::from(solution.anagrams.len())
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn parallel_solves_match_solve() {
+        let solver = test_solver();
+        let sorted = |solution: Solution| {
+            let mut anagrams = solution
+                .anagrams
+                .iter()
+                .map(Anagram::to_string)
+                .collect::<Vec<_>>();
+            anagrams.sort_unstable();
+            (anagrams, solution.partials, solution.truncated)
+        };
+        for seed in ["stop a rat", "sprat", "post it", "zzz"] {
+            for includes in [vec![], vec!["star".to_string()], vec!["zoo".to_string()]] {
+                for max_results in [None, Some(1), Some(7), Some(100)] {
+                    let mut query = Query::new(seed);
+                    query.min_length = 1;
+                    query.includes = includes.clone();
+                    query.budget.max_results = max_results;
+                    let expected = sorted(solver.solve(&query).unwrap());
+                    assert_eq!(
+                        sorted(solver.solve_parallel(&query).unwrap()),
+                        expected,
+                        "{:?} with {:?} and {:?}",
+                        seed,
+                        includes,
+                        max_results
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn saved_indexes_keep_added_and_removed_words() {
+        let mut dictionary = WordList::new();
+        for (word, count) in [("stop", 9), ("post", 8), ("rat", 7), ("tar", 6), ("a", 5)] {
+            dictionary.push(word, count);
+        }
+        let mut solver = AnagramSolver::from_dictionary(&dictionary).unwrap();
+        solver.add_word("new york", Some(4)).unwrap();
+        solver.add_word("spot", None).unwrap();
+        solver.remove_word("post");
+
+        let loaded = AnagramSolver::from_index(&solver.to_index()).unwrap();
+        assert_eq!(loaded.len(), solver.len());
+        assert_eq!(loaded.index.word_id("post"), None);
+        assert_unique_keys(&loaded.index);
+        for seed in ["stop rat", "tops art a", "york new"] {
+            let mut query = Query::new(seed);
+            query.min_length = 1;
+            assert_eq!(
+                loaded.solve(&query).unwrap().anagrams,
+                solver.solve(&query).unwrap().anagrams
+            );
+        }
+    }
 }
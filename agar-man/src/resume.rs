@@ -0,0 +1,289 @@
+// Resume tokens, for picking a search up where an earlier one left off. A token holds a
+// fingerprint of the query and dictionary it belongs to, then where the search stood: whether it
+// was between anagrams or partway through the words of one, how far it had got through the keys
+// of its deepest frame, and a word for each key on its path. All of it is in hex so it can travel
+// in a URL.
+
+use crate::{AgarError, Query};
+
+/** FNV-1a, which unlike the std hashers gives the same result in every build. */
+pub(crate) struct Fingerprint(pub(crate) u64);
+
+impl Fingerprint {
+    pub(crate) fn new() -> Self {
+        Fingerprint(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn write_number(&mut self, number: usize) {
+        self.write(&(number as u64).to_le_bytes());
+    }
+
+    pub(crate) fn write_str(&mut self, s: &str) {
+        // the length keeps ["ab", "c"] apart from ["a", "bc"]
+        self.write_number(s.len());
+        self.write(s.as_bytes());
+    }
+}
+
+/**
+ * What a token is valid for: the query, except for its budget, and the dictionary with the given
+ * generation. Word ids and counts change with the dictionary.
+ */
+pub(crate) fn fingerprint(query: &Query, generation: u64) -> u64 {
+    let mut fingerprint = Fingerprint::new();
+    fingerprint.write_str(&query.seed);
+    fingerprint.write_number(query.min_length);
+    fingerprint.write_number(query.max_num_words);
+    fingerprint.write_number(query.top_n);
+
+    fingerprint.write_number(query.includes.len());
+    for word in &query.includes {
+        fingerprint.write_str(word);
+    }
+    // sets have no order of their own
+    let mut excludes = query.excludes.iter().collect::<Vec<_>>();
+    excludes.sort_unstable();
+    fingerprint.write_number(excludes.len());
+    for word in excludes {
+        fingerprint.write_str(word);
+    }
+
+    fingerprint.write(&generation.to_le_bytes());
+    fingerprint.0
+}
+
+/** Where a search stood when its token was made. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stage {
+    // looking for the next path of keys, from `position` in the deepest frame
+    Searching,
+    // expanding a path into words, having returned the current words
+    Returned,
+    // expanding a path into words, with the current words not yet returned
+    Pending,
+}
+
+/** The state of a search, as carried by a token. */
+pub(crate) struct Checkpoint {
+    pub(crate) fingerprint: u64,
+    pub(crate) stage: Stage,
+    pub(crate) position: usize,
+    pub(crate) words: Vec<usize>,
+}
+
+pub(crate) fn encode(checkpoint: &Checkpoint) -> String {
+    let stage = match checkpoint.stage {
+        Stage::Searching => 0,
+        Stage::Returned => 1,
+        Stage::Pending => 2,
+    };
+    let mut token = format!(
+        "{:016x}{:02x}{:08x}",
+        checkpoint.fingerprint, stage, checkpoint.position
+    );
+    for &id in &checkpoint.words {
+        token.push_str(&format!("{:08x}", id));
+    }
+    token
+}
+
+fn invalid(reason: &str) -> AgarError {
+    AgarError::InvalidResumeToken {
+        reason: reason.to_string(),
+    }
+}
+
+pub(crate) fn decode(token: &str) -> Result<Checkpoint, AgarError> {
+    const HEADER: usize = 16 + 2 + 8;
+    if !token.is_ascii() || token.len() < HEADER || !(token.len() - HEADER).is_multiple_of(8) {
+        return Err(invalid("malformed"));
+    }
+    let number = |digits: &str| u64::from_str_radix(digits, 16).map_err(|_| invalid("malformed"));
+
+    let stage = match number(&token[16..18])? {
+        0 => Stage::Searching,
+        1 => Stage::Returned,
+        2 => Stage::Pending,
+        _ => return Err(invalid("malformed")),
+    };
+    let words = (HEADER..token.len())
+        .step_by(8)
+        .map(|i| number(&token[i..i + 8]).map(|id| id as usize))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Checkpoint {
+        fingerprint: number(&token[..16])?,
+        stage,
+        position: number(&token[18..HEADER])? as usize,
+        words,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_solver as solver;
+    use crate::{AnagramSolver, Budget};
+
+    fn query() -> Query {
+        let mut query = Query::new("stop a rat");
+        query.min_length = 1;
+        query.max_num_words = 4;
+        query
+    }
+
+    /** The anagrams of `query` found by resuming after every page the budget allows. */
+    fn paged(solver: &AnagramSolver, query: &Query) -> (Vec<Vec<String>>, usize) {
+        let mut anagrams = solver.anagrams(query).unwrap();
+        let mut found = Vec::new();
+        let mut pages = 1;
+        loop {
+            found.extend(anagrams.by_ref().map(|anagram| anagram.words));
+            match anagrams.resume_token() {
+                Some(token) => anagrams = solver.resume(query, &token).unwrap(),
+                None => return (found, pages),
+            }
+            pages += 1;
+        }
+    }
+
+    fn token(solver: &AnagramSolver) -> String {
+        let mut query = query();
+        query.budget.max_results = Some(5);
+        let mut anagrams = solver.anagrams(&query).unwrap();
+        assert_eq!(anagrams.by_ref().count(), 5);
+        anagrams.resume_token().unwrap()
+    }
+
+    #[test]
+    fn resumed_pages_match_a_full_run() {
+        let solver = solver();
+        let full = solver
+            .anagrams(&query())
+            .unwrap()
+            .map(|anagram| anagram.words)
+            .collect::<Vec<_>>();
+        assert!(full.len() > 100);
+
+        for max_results in [1, 2, 7, 50] {
+            let mut query = query();
+            query.budget.max_results = Some(max_results);
+            let (found, pages) = paged(&solver, &query);
+            assert_eq!(found, full, "pages of {}", max_results);
+            assert!(pages >= full.len().div_ceil(max_results));
+        }
+        for max_nodes in [1, 3, 20, 100] {
+            let mut query = query();
+            query.budget = Budget {
+                max_nodes: Some(max_nodes),
+                ..Budget::default()
+            };
+            let (found, pages) = paged(&solver, &query);
+            assert_eq!(found, full, "pages of {} nodes", max_nodes);
+            assert!(pages > 1);
+        }
+    }
+
+    #[test]
+    fn finished_searches_have_no_token() {
+        let solver = solver();
+        let mut anagrams = solver.anagrams(&query()).unwrap();
+        assert!(anagrams.by_ref().count() > 0);
+        assert_eq!(anagrams.resume_token(), None);
+    }
+
+    #[test]
+    fn tokens_round_trip() {
+        let checkpoint = Checkpoint {
+            fingerprint: 0x0123_4567_89ab_cdef,
+            stage: Stage::Pending,
+            position: 42,
+            words: vec![0, 7, 0xffff_ffff],
+        };
+        let decoded = decode(&encode(&checkpoint)).unwrap();
+        assert_eq!(decoded.fingerprint, checkpoint.fingerprint);
+        assert_eq!(decoded.stage, checkpoint.stage);
+        assert_eq!(decoded.position, checkpoint.position);
+        assert_eq!(decoded.words, checkpoint.words);
+    }
+
+    #[test]
+    fn tampered_tokens_are_rejected() {
+        let solver = solver();
+        let token = token(&solver);
+        assert!(solver.resume(&query(), &token).is_ok());
+
+        let replace = |at: usize, digits: &str| {
+            let mut tampered = token.clone();
+            tampered.replace_range(at..at + digits.len(), digits);
+            tampered
+        };
+        let flip = |at: usize| {
+            let digit = if &token[at..at + 1] == "0" { "1" } else { "0" };
+            replace(at, digit)
+        };
+        let rejected = [
+            // the fingerprint, the stage, the position and a word
+            flip(0),
+            flip(15),
+            replace(16, "03"),
+            replace(18, "ffffffff"),
+            replace(26, "ffffffff"),
+            token[..token.len() - 1].to_string(),
+            format!("{}00000000", token),
+            token.replace(|c: char| c.is_ascii_digit(), "g"),
+            String::new(),
+        ];
+        for tampered in rejected {
+            assert!(
+                matches!(
+                    solver.resume(&query(), &tampered),
+                    Err(AgarError::InvalidResumeToken { .. })
+                ),
+                "{:?} was accepted",
+                tampered
+            );
+        }
+
+        // whatever else is changed, resuming must fail cleanly or carry on with a valid search
+        for at in 16..token.len() {
+            for digit in ["0", "1", "7", "f"] {
+                if let Ok(anagrams) = solver.resume(&query(), &replace(at, digit)) {
+                    anagrams.take(1000).for_each(drop);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tokens_are_tied_to_their_query_and_dictionary() {
+        let mut solver = solver();
+        let token = token(&solver);
+
+        let mut other = query();
+        other.excludes.insert("rat".to_string());
+        assert!(solver.resume(&other, &token).is_err());
+        // the budget is not part of the query a token belongs to
+        let mut budgeted = query();
+        budgeted.budget.max_results = Some(1);
+        assert!(solver.resume(&budgeted, &token).is_ok());
+
+        solver.add_word("ta", None).unwrap();
+        assert!(solver.resume(&query(), &token).is_err());
+
+        // even a change that leaves the number of words the same
+        let mut solver = self::solver();
+        solver.add_word("ta", None).unwrap();
+        solver.remove_word("ta");
+        assert!(solver.resume(&query(), &token).is_err());
+        let mut solver = self::solver();
+        solver.add_word("rat", Some(1)).unwrap();
+        assert!(solver.resume(&query(), &token).is_err());
+    }
+}
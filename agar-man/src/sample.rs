@@ -0,0 +1,171 @@
+use num_bigint::BigUint;
+
+/** How `AnagramSolver::sample` chooses among the anagrams of a query. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    // every anagram is equally likely
+    #[default]
+    Uniform,
+    // anagrams are weighted by the product of one more than the count of each of their words, so
+    // common words come up more often
+    Frequency,
+}
+
+/**
+ * A small seedable generator (SplitMix64), so that a sample can be drawn again from its seed, on
+ * any platform.
+ */
+#[derive(Clone, Debug)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /** A uniformly random number below `bound`, which must not be zero. */
+    pub(crate) fn below(&mut self, bound: &BigUint) -> BigUint {
+        let bits = bound.bits();
+        let num_digits = bits.div_ceil(32) as usize;
+        // draw numbers of as many bits as the bound until one is below it, which takes fewer than
+        // two tries on average
+        loop {
+            let digits = (0..num_digits)
+                .map(|_| self.next_u64() as u32)
+                .collect::<Vec<_>>();
+            let number = BigUint::from_slice(&digits) >> (num_digits as u64 * 32 - bits);
+            if number < *bound {
+                return number;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_solver_with;
+    use crate::{Anagram, AnagramSolver, Query};
+    use std::collections::HashMap;
+
+    fn solver() -> AnagramSolver {
+        // spread the counts out, so weighting by frequency is far from uniform
+        test_solver_with(|rank| 10 * (rank as u32 % 7))
+    }
+
+    fn query() -> Query {
+        let mut query = Query::new("star tap");
+        query.min_length = 1;
+        query
+    }
+
+    fn key(anagram: &Anagram) -> Vec<String> {
+        let mut words = anagram.words.clone();
+        words.sort_unstable();
+        words
+    }
+
+    /** Pearson's chi-squared statistic for the samples against the expected weights. */
+    fn chi_squared(samples: &[Anagram], weights: &HashMap<Vec<String>, f64>) -> f64 {
+        let mut observed = HashMap::new();
+        for anagram in samples {
+            let key = key(anagram);
+            assert!(weights.contains_key(&key), "{:?} is not an anagram", key);
+            *observed.entry(key).or_insert(0.0) += 1.0;
+        }
+        let total_weight = weights.values().sum::<f64>();
+        weights
+            .iter()
+            .map(|(key, weight)| {
+                let expected = samples.len() as f64 * weight / total_weight;
+                let observed = observed.get(key).copied().unwrap_or(0.0);
+                (observed - expected) * (observed - expected) / expected
+            })
+            .sum()
+    }
+
+    /** Far beyond the chi-squared distribution's bulk, so a fixed seed cannot fail by chance. */
+    fn critical_value(num_anagrams: usize) -> f64 {
+        let degrees = (num_anagrams - 1) as f64;
+        degrees + 6.0 * (2.0 * degrees).sqrt()
+    }
+
+    #[test]
+    fn below_is_in_range_and_covers_it() {
+        let mut rng = Rng::new(7);
+        let bound = BigUint::from(10u32);
+        let mut seen = [0; 10];
+        for _ in 0..1000 {
+            let number = rng.below(&bound);
+            assert!(number < bound);
+            seen[number.to_u32_digits().first().copied().unwrap_or(0) as usize] += 1;
+        }
+        assert!(seen.iter().all(|&times| times > 50));
+    }
+
+    #[test]
+    fn samples_are_reproducible() {
+        let solver = solver();
+        let draw = |seed| {
+            solver
+                .sample(&query(), 50, Sampling::Uniform, seed)
+                .unwrap()
+                .anagrams
+        };
+        assert_eq!(draw(1), draw(1));
+        assert_ne!(draw(1), draw(2));
+    }
+
+    #[test]
+    fn uniform_samples_are_uniform() {
+        let solver = solver();
+        let anagrams = solver.solve(&query()).unwrap().anagrams;
+        assert!(anagrams.len() > 10);
+        let weights = anagrams
+            .iter()
+            .map(|anagram| (key(anagram), 1.0))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(weights.len(), anagrams.len());
+
+        let samples = solver
+            .sample(&query(), 200 * anagrams.len(), Sampling::Uniform, 42)
+            .unwrap()
+            .anagrams;
+        assert_eq!(samples.len(), 200 * anagrams.len());
+        assert!(chi_squared(&samples, &weights) < critical_value(anagrams.len()));
+    }
+
+    #[test]
+    fn frequency_samples_follow_word_counts() {
+        let solver = solver();
+        let anagrams = solver.solve(&query()).unwrap().anagrams;
+        let weights = anagrams
+            .iter()
+            .map(|anagram| {
+                let weight = anagram
+                    .per_word_counts
+                    .iter()
+                    .map(|&count| count as f64 + 1.0)
+                    .product::<f64>();
+                (key(anagram), weight)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let samples = solver
+            .sample(&query(), 200 * anagrams.len(), Sampling::Frequency, 42)
+            .unwrap()
+            .anagrams;
+        assert!(chi_squared(&samples, &weights) < critical_value(anagrams.len()));
+        // and not uniformly
+        let uniform = weights.keys().map(|key| (key.clone(), 1.0)).collect();
+        assert!(chi_squared(&samples, &uniform) > critical_value(anagrams.len()));
+    }
+}
@@ -0,0 +1,504 @@
+// Ways of ranking anagrams. Counts are 100 * log2 of how much more often a word occurs than the
+// rarest ones, so 100 counts is a factor of two in frequency.
+
+use crate::{letter_count, AgarError, Bigrams};
+use std::fmt;
+use std::sync::Arc;
+
+/** Ranks anagrams by the words a search found for them; included words do not count. */
+pub trait Scorer: fmt::Debug + Send + Sync {
+    /**
+     * The score of the found words, given their counts in the dictionary, with higher being
+     * better. There is always at least one word.
+     */
+    fn score(&self, words: &[String], counts: &[u32]) -> f32;
+
+    /**
+     * The highest score any anagram can reach from found words with `counts`, given between
+     * `min_more` and `max_more` more words with counts of at most `most`, or `None` if this
+     * scorer cannot tell. `solve_best` prunes its search with this, so it must never be below a
+     * reachable score, and must not go down when a count goes up. Without it, `solve_best` ranks
+     * every anagram there is. Scorers that give bounds also say so with `has_bound`.
+     */
+    fn bound(
+        &self,
+        _counts: &[u32],
+        _min_more: usize,
+        _max_more: usize,
+        _most: u32,
+    ) -> Option<f64> {
+        None
+    }
+
+    /** Whether `bound` gives bounds, so that `solve_best` can prune rather than rank everything. */
+    fn has_bound(&self) -> bool {
+        false
+    }
+
+    /**
+     * The order to put the found words in before scoring them, as indices into `words`, or
+     * `None` to keep the order they were found in.
+     */
+    fn arrange(&self, _words: &[String], _counts: &[u32]) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+/** The mean count, which favours short common words such as "the" and "a". */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Mean;
+
+impl Scorer for Mean {
+    fn score(&self, _words: &[String], counts: &[u32]) -> f32 {
+        counts.iter().map(|&count| count as f32).sum::<f32>() / counts.len() as f32
+    }
+
+    fn bound(&self, counts: &[u32], min_more: usize, max_more: usize, most: u32) -> Option<f64> {
+        let values = counts.iter().map(|&count| count as f64);
+        Some(mean_bound(values, min_more, max_more, most as f64))
+    }
+
+    fn has_bound(&self) -> bool {
+        true
+    }
+}
+
+/**
+ * The highest mean of `values` and between `min_more` and `max_more` more values of at most
+ * `most`. Adding values raises the mean only if they are above it.
+ */
+fn mean_bound(
+    values: impl ExactSizeIterator<Item = f64>,
+    min_more: usize,
+    max_more: usize,
+    most: f64,
+) -> f64 {
+    let len = values.len();
+    let sum = values.sum::<f64>();
+    let more = if most * len as f64 > sum {
+        max_more
+    } else {
+        min_more
+    };
+    (sum + more as f64 * most) / (len + more) as f64
+}
+
+/** The geometric mean of one more than each count, less one, which punishes rare words more. */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GeometricMean;
+
+impl Scorer for GeometricMean {
+    fn score(&self, _words: &[String], counts: &[u32]) -> f32 {
+        let log_sum = counts
+            .iter()
+            .map(|&count| (count as f64 + 1.0).ln())
+            .sum::<f64>();
+        ((log_sum / counts.len() as f64).exp() - 1.0) as f32
+    }
+
+    // the mean of the logs, bounded as `Mean` bounds the counts
+    fn bound(&self, counts: &[u32], min_more: usize, max_more: usize, most: u32) -> Option<f64> {
+        let logs = counts.iter().map(|&count| (count as f64 + 1.0).ln());
+        let most = (most as f64 + 1.0).ln();
+        Some(mean_bound(logs, min_more, max_more, most).exp() - 1.0)
+    }
+
+    fn has_bound(&self) -> bool {
+        true
+    }
+}
+
+/** The count of the rarest word, so an anagram is only as good as its weakest word. */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinCount;
+
+impl Scorer for MinCount {
+    fn score(&self, _words: &[String], counts: &[u32]) -> f32 {
+        counts.iter().copied().min().unwrap_or(0) as f32
+    }
+
+    // more words can only lower the minimum
+    fn bound(&self, counts: &[u32], min_more: usize, _max_more: usize, most: u32) -> Option<f64> {
+        let min = counts.iter().copied().min().unwrap_or(most);
+        Some(if min_more > 0 { min.min(most) } else { min } as f64)
+    }
+
+    fn has_bound(&self) -> bool {
+        true
+    }
+}
+
+/**
+ * Minus the number of words, for blending with another scorer: `mean+100*fewer-words` takes 100
+ * counts off for every word.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FewerWords;
+
+impl Scorer for FewerWords {
+    fn score(&self, words: &[String], _counts: &[u32]) -> f32 {
+        -(words.len() as f32)
+    }
+
+    fn bound(&self, counts: &[u32], min_more: usize, _max_more: usize, _most: u32) -> Option<f64> {
+        Some(-((counts.len() + min_more) as f64))
+    }
+
+    fn has_bound(&self) -> bool {
+        true
+    }
+}
+
+/** The number of letters in the longest word, for blending with another scorer. */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LongestWord;
+
+impl Scorer for LongestWord {
+    fn score(&self, words: &[String], _counts: &[u32]) -> f32 {
+        words
+            .iter()
+            .map(|word| letter_count(word))
+            .max()
+            .unwrap_or(0) as f32
+    }
+}
+
+/** A weighted sum of other scorers. */
+#[derive(Clone, Debug, Default)]
+pub struct Blend(pub Vec<(f32, Arc<dyn Scorer>)>);
+
+impl Scorer for Blend {
+    fn score(&self, words: &[String], counts: &[u32]) -> f32 {
+        self.0
+            .iter()
+            .map(|(weight, scorer)| weight * scorer.score(words, counts))
+            .sum()
+    }
+
+    // a negative weight would need a lower bound on its scorer instead
+    fn bound(&self, counts: &[u32], min_more: usize, max_more: usize, most: u32) -> Option<f64> {
+        self.0
+            .iter()
+            .map(|(weight, scorer)| {
+                let bound = scorer.bound(counts, min_more, max_more, most)?;
+                (*weight >= 0.0).then_some(*weight as f64 * bound)
+            })
+            .sum()
+    }
+
+    fn has_bound(&self) -> bool {
+        self.0
+            .iter()
+            .all(|(weight, scorer)| *weight >= 0.0 && scorer.has_bound())
+    }
+
+    // the first scorer with an opinion on the order decides it
+    fn arrange(&self, words: &[String], counts: &[u32]) -> Option<Vec<usize>> {
+        self.0
+            .iter()
+            .find_map(|(_, scorer)| scorer.arrange(words, counts))
+    }
+}
+
+// every order of up to this many words is considered, which takes 2^n * n^2 steps
+const MAX_ARRANGED_WORDS: usize = 10;
+
+/**
+ * How natural the words sound as a phrase: the mean count of the words and of each pair of
+ * neighbouring words, after putting the words in the order whose pairs are the most common.
+ * Pairs missing from the table count as 0, as if seen no more often than the cut-off that
+ * `aggregate_2grams` drops pairs below, so "mango tree" beats "tree mango".
+ */
+#[derive(Clone, Debug)]
+pub struct BigramScorer(pub Arc<Bigrams>);
+
+impl BigramScorer {
+    /** The count of word `a` followed by word `b`. */
+    fn pair_count(&self, ids: &[Option<u32>], a: usize, b: usize) -> f32 {
+        self.0.pair_count(ids[a], ids[b]).unwrap_or(0) as f32
+    }
+
+    fn ids(&self, words: &[String]) -> Vec<Option<u32>> {
+        words.iter().map(|word| self.0.word_id(word)).collect()
+    }
+}
+
+impl Scorer for BigramScorer {
+    fn score(&self, words: &[String], counts: &[u32]) -> f32 {
+        let ids = self.ids(words);
+        let pairs = (1..words.len())
+            .map(|i| self.pair_count(&ids, i - 1, i))
+            .sum::<f32>();
+        let singles = counts.iter().map(|&count| count as f32).sum::<f32>();
+        (singles + pairs) / (2 * words.len() - 1) as f32
+    }
+
+    fn arrange(&self, words: &[String], _counts: &[u32]) -> Option<Vec<usize>> {
+        let n = words.len();
+        if !(2..=MAX_ARRANGED_WORDS).contains(&n) {
+            return None;
+        }
+        let ids = self.ids(words);
+        // look every pair up once, rather than at each step below; row `a` holds `a` then `b`
+        let mut pairs = vec![0.0; n * n];
+        let mut seen = false;
+        for a in 0..n {
+            for b in (0..n).filter(|&b| b != a) {
+                if let Some(count) = self.0.pair_count(ids[a], ids[b]) {
+                    pairs[a * n + b] = count as f32;
+                    seen = true;
+                }
+            }
+        }
+        // without a single pair to go by, keep the order the words were found in
+        if !seen {
+            return None;
+        }
+
+        // the most common pairs for ordering each set of words so that it ends with each word in
+        // it, and the word before that last one
+        let mut best = vec![f32::NEG_INFINITY; n << n];
+        let mut previous = vec![usize::MAX; n << n];
+        for i in 0..n {
+            best[(n << i) + i] = 0.0;
+        }
+        for set in 1..1 << n {
+            for last in (0..n).filter(|&last| set & (1 << last) != 0) {
+                let total = best[set * n + last];
+                for next in (0..n).filter(|&next| set & (1 << next) == 0) {
+                    let entry = (set | (1 << next)) * n + next;
+                    let extended = total + pairs[last * n + next];
+                    if extended > best[entry] {
+                        best[entry] = extended;
+                        previous[entry] = last;
+                    }
+                }
+            }
+        }
+
+        // walk back from the best last word
+        let mut set = (1 << n) - 1;
+        let mut last = (0..n)
+            .max_by(|&a, &b| best[set * n + a].total_cmp(&best[set * n + b]))
+            .unwrap_or(0);
+        let mut order = Vec::with_capacity(n);
+        while last != usize::MAX {
+            order.push(last);
+            let before = previous[set * n + last];
+            set &= !(1 << last);
+            last = before;
+        }
+        order.reverse();
+        Some(order)
+    }
+}
+
+fn builtin(name: &str) -> Option<Arc<dyn Scorer>> {
+    Some(match name {
+        "mean" => Arc::new(Mean),
+        "geometric" => Arc::new(GeometricMean),
+        "min" => Arc::new(MinCount),
+        "fewer-words" => Arc::new(FewerWords),
+        "longest-word" => Arc::new(LongestWord),
+        _ => return None,
+    })
+}
+
+/**
+ * A scorer by name: `mean`, `geometric`, `min`, `fewer-words` or `longest-word`, or a blend of
+ * them such as `mean+100*fewer-words+20*longest-word`.
+ */
+pub fn parse_scorer(spec: &str) -> Result<Arc<dyn Scorer>, AgarError> {
+    let invalid = |reason: String| AgarError::InvalidScorer {
+        spec: spec.to_string(),
+        reason,
+    };
+
+    let mut terms = Vec::new();
+    for term in spec.split('+').map(str::trim) {
+        let (weight, name) = match term.split_once('*') {
+            Some((weight, name)) => {
+                let weight = weight
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|weight| weight.is_finite())
+                    .ok_or_else(|| invalid(format!("{:?} is not a weight", weight.trim())))?;
+                (weight, name.trim())
+            }
+            None => (1.0, term),
+        };
+        let scorer = builtin(name).ok_or_else(|| invalid(format!("unknown scorer {:?}", name)))?;
+        terms.push((weight, scorer));
+    }
+
+    // a lone scorer needs no blend around it
+    match terms.as_slice() {
+        [(weight, scorer)] if *weight == 1.0 => Ok(scorer.clone()),
+        _ => Ok(Arc::new(Blend(terms))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    fn score(spec: &str, found: &[&str], counts: &[u32]) -> f32 {
+        parse_scorer(spec).unwrap().score(&words(found), counts)
+    }
+
+    #[test]
+    fn specs_are_parsed() {
+        let found = ["new york", "a", "rat"];
+        let counts = [300, 600, 0];
+        assert_eq!(score("mean", &found, &counts), 300.0);
+        assert_eq!(score("geometric", &["a", "b"], &[0, 3]), 1.0);
+        assert_eq!(score("min", &found, &counts), 0.0);
+        assert_eq!(score("fewer-words", &found, &counts), -3.0);
+        assert_eq!(score("longest-word", &found, &counts), 7.0);
+        assert_eq!(score(" 2 * mean ", &found, &counts), 600.0);
+        assert_eq!(
+            score("mean+100*fewer-words+0.5*longest-word", &found, &counts),
+            300.0 - 300.0 + 3.5
+        );
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        for spec in [
+            "median", "", "mean+", "2*", "*mean", "two*mean", "inf*mean", "2*3*mean",
+        ] {
+            assert!(
+                matches!(parse_scorer(spec), Err(AgarError::InvalidScorer { .. })),
+                "{:?} was accepted",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn scorers_score_their_words() {
+        let found = words(&["stop", "a", "rat"]);
+        let counts = [100, 700, 400];
+        assert_eq!(Mean.score(&found, &counts), 400.0);
+        // (1 * 4 * 16)^(1/3) - 1
+        assert!((GeometricMean.score(&found, &[0, 3, 15]) - 3.0).abs() < 1e-4);
+        assert_eq!(MinCount.score(&found, &counts), 100.0);
+        assert_eq!(FewerWords.score(&found, &counts), -3.0);
+        assert_eq!(LongestWord.score(&found, &counts), 4.0);
+        let blend = Blend(vec![(1.0, Arc::new(Mean)), (-2.0, Arc::new(MinCount))]);
+        assert_eq!(blend.score(&found, &counts), 200.0);
+    }
+
+    #[test]
+    fn bounds_are_never_beaten() {
+        let scorers = [
+            "mean",
+            "geometric",
+            "min",
+            "fewer-words",
+            "2*mean+100*fewer-words",
+        ];
+        for spec in scorers {
+            let scorer = parse_scorer(spec).unwrap();
+            for (counts, most) in [(vec![500], 200), (vec![100, 300], 800), (vec![0], 0)] {
+                let bound = scorer.bound(&counts, 0, 0, most).unwrap();
+                let score = scorer.score(&words(&vec!["a"; counts.len()]), &counts);
+                assert!(
+                    (bound - score as f64).abs() < 1e-3,
+                    "{} of {:?}",
+                    spec,
+                    counts
+                );
+
+                // every way of adding up to two words with counts of at most `most`
+                let bound = scorer.bound(&counts, 0, 2, most).unwrap();
+                for more in [vec![], vec![most], vec![most, most], vec![0, most / 2]] {
+                    let all = [counts.clone(), more].concat();
+                    let score = scorer.score(&words(&vec!["a"; all.len()]), &all);
+                    assert!(bound >= score as f64 - 1e-3, "{} of {:?}", spec, all);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn only_some_scorers_have_bounds() {
+        for spec in [
+            "mean",
+            "geometric",
+            "min",
+            "fewer-words",
+            "mean+100*fewer-words",
+        ] {
+            assert!(parse_scorer(spec).unwrap().has_bound(), "{}", spec);
+        }
+        for spec in ["longest-word", "mean+longest-word", "-1*mean"] {
+            assert!(!parse_scorer(spec).unwrap().has_bound(), "{}", spec);
+        }
+        assert!(parse_scorer("longest-word")
+            .unwrap()
+            .bound(&[1], 0, 1, 1)
+            .is_none());
+        assert!(parse_scorer("mean+longest-word")
+            .unwrap()
+            .bound(&[1], 0, 1, 1)
+            .is_none());
+        // a bound on a scorer is no bound on minus that scorer
+        assert!(parse_scorer("-1*mean")
+            .unwrap()
+            .bound(&[1], 0, 1, 1)
+            .is_none());
+        let bigrams = BigramScorer(Arc::new(Bigrams::default()));
+        assert!(bigrams.bound(&[1], 0, 1, 1).is_none());
+        assert!(!bigrams.has_bound());
+    }
+
+    fn bigrams() -> BigramScorer {
+        let mut bigrams = Bigrams::new();
+        bigrams.push("mango", "tree", 500);
+        bigrams.push("tree", "house", 300);
+        bigrams.push("house", "mango", 100);
+        BigramScorer(Arc::new(bigrams))
+    }
+
+    #[test]
+    fn bigrams_pick_the_likeliest_order() {
+        let scorer = bigrams();
+        let found = words(&["tree", "mango"]);
+        assert_eq!(scorer.arrange(&found, &[200, 100]), Some(vec![1, 0]));
+        // (100 + 200 + 500) / 3 for "mango tree", against no pair at all for "tree mango"
+        let mango_tree = words(&["mango", "tree"]);
+        assert_eq!(scorer.score(&mango_tree, &[100, 200]), 800.0 / 3.0);
+        assert_eq!(scorer.score(&found, &[200, 100]), 100.0);
+
+        // "mango tree house" has the two most common pairs
+        let found = words(&["house", "tree", "mango"]);
+        assert_eq!(scorer.arrange(&found, &[0, 0, 0]), Some(vec![2, 1, 0]));
+    }
+
+    #[test]
+    fn bigrams_keep_the_order_found_without_pairs() {
+        let scorer = bigrams();
+        assert_eq!(scorer.arrange(&words(&["tree"]), &[200]), None);
+        for found in [
+            &["star", "tree"][..],
+            &["house", "star"],
+            &["stop", "a", "rat"],
+        ] {
+            assert_eq!(
+                scorer.arrange(&words(found), &[0, 0, 0][..found.len()]),
+                None
+            );
+        }
+        // the pairs that are known still decide the order
+        let found = words(&["house", "star", "tree"]);
+        let order = scorer.arrange(&found, &[0, 0, 0]).unwrap();
+        let position = |i| order.iter().position(|&j| j == i).unwrap();
+        assert_eq!(position(0), position(2) + 1);
+    }
+}
@@ -1,8 +1,24 @@
+use crate::budget::{Budget, CancellationToken, Stopwatch, Truncation};
+use crate::{
+    counter_contains, letter_count, normalize_word, rank_words, subtract_counters, to_counter,
+    to_index, AgarError, Anagram, Counter, Dictionary, Engine, Query, Solution, ALPHA_SIZE,
+};
+use std::collections::HashSet;
+use std::ptr;
+
+/** Whether `current_word` sorts before `previous_word`, comparing only as far as both go. */
+fn compare_char_array(current_word: &[usize], previous_word: &[usize]) -> bool {
+    let length = current_word.len().min(previous_word.len());
+    current_word[..length] < previous_word[..length]
+}
 
 #[derive(Default)]
 struct Trie {
-    children: [Option<Box<Trie>>; ALPHA_SIZE], // See https://doc.rust-lang.org/book/ch15-01-box.html#enabling-recursive-types-with-boxes
-    end_of_word: bool,
+    // boxed, since a node cannot hold its children directly; see
+    // https://doc.rust-lang.org/book/ch15-01-box.html#enabling-recursive-types-with-boxes
+    children: [Option<Box<Trie>>; ALPHA_SIZE],
+    // ids of the words spelled out by the path to this node
+    words: Vec<usize>,
 }
 
 impl Trie {
@@ -10,134 +26,326 @@ impl Trie {
         Default::default()
     }
 
-    /** Insert a word. */
-    fn insert(&mut self, word: String) {
+    /** Insert a word, ignoring the spaces in phrases. */
+    fn insert(&mut self, word: &str, id: usize) {
         let mut node = self;
-        for i in word.chars().map(to_index) {
-            node = node.children[i].get_or_insert(Box::new(Trie::new()));
+        for i in word.chars().filter(|&c| c != ' ').map(to_index) {
+            node = node.children[i].get_or_insert_with(|| Box::new(Trie::new()));
         }
-        node.end_of_word = true;
+        node.words.push(id);
     }
+}
 
-    /** Outer anagram function */
-    fn anagram(&self, seed: &String) -> Vec<String> {
-        let seed_counter = to_counter(seed);
-
-        let mut results = Vec::new();
-        let mut path = Vec::new();
+/**
+ * Find anagrams of `seed` with any number of words, returning them with every word that fits in
+ * the seed. A shorthand for a `TrieEngine` query, kept for callers of the original trie solver.
+ */
+pub fn trie_solve(
+    dictionary: &dyn Dictionary,
+    seed: &str,
+    min_length: usize,
+    excludes: &HashSet<String>,
+) -> Result<(Vec<String>, Vec<String>), AgarError> {
+    let query = Query {
+        min_length,
+        // the original trie solver never limited the number of words, and no anagram has more
+        // words than letters
+        max_num_words: letter_count(seed),
+        excludes: excludes.clone(),
+        ..Query::new(seed)
+    };
+    let solution = TrieEngine::new(dictionary)?.solve(&query)?;
+    Ok((
+        solution.anagrams.iter().map(Anagram::to_string).collect(),
+        solution.partials,
+    ))
+}
 
-        let mut current_word = Vec::new();
-        let mut previous_word = Vec::new();
+/**
+ * A depth-first search over the letter trie, emitting the words of each complete anagram. Words
+ * are taken in alphabetical order of their letters, so each combination is only found once.
+ */
+struct TrieSearch<'a> {
+    root: &'a Trie,
+    min_length: usize,
+    max_num_words: usize,
+    target_counter: Counter,
+    letters_left: usize,
+    // letters of the words chosen so far, and where each word (including the current one) starts
+    letters: Vec<usize>,
+    starts: Vec<usize>,
+    // the word ids for each complete word on the path
+    path: Vec<&'a [usize]>,
+    found: Vec<Vec<usize>>,
+    budget: Budget,
+    cancellation: &'a CancellationToken,
+    stopwatch: Stopwatch,
+    nodes: u64,
+    truncated: Option<Truncation>,
+}
 
-        self.anagram_recursive(
-            seed_counter,
-            &mut path,
-            self,
-            &mut current_word,
-            &mut previous_word,
-            &mut results,
-        );
+impl<'a> TrieSearch<'a> {
+    fn current_word(&self) -> &[usize] {
+        &self.letters[self.starts[self.starts.len() - 1]..]
+    }
 
-        return results;
+    fn previous_word(&self) -> &[usize] {
+        match self.starts.len() {
+            1 => &[],
+            n => &self.letters[self.starts[n - 2]..self.starts[n - 1]],
+        }
     }
 
-    /** Inner anagram function */
-    fn anagram_recursive(
-        &self,
-        mut seed_counter: Counter,
-        path: &mut Vec<usize>,
-        root: &Trie,
-        current_word: &mut Vec<usize>,
-        previous_word: &mut Vec<usize>,
-        results: &mut Vec<String>,
-    ) {
-        if self.end_of_word {
-            // if all characters have been used
-            if seed_counter == [0; ALPHA_SIZE] {
-                results.push(array_to_string(&path));
+    fn search(&mut self, node: &'a Trie) {
+        if !node.words.is_empty() && self.current_word() >= self.previous_word() {
+            self.path.push(&node.words);
+            if self.letters_left == 0 {
+                self.expand();
+            } else if self.path.len() < self.max_num_words && self.letters_left >= self.min_length {
+                self.starts.push(self.letters.len());
+                self.search(self.root);
+                self.starts.pop();
             }
-            path.push(27);
-            let old_word = previous_word.clone();
-            previous_word.clear();
-            previous_word.extend_from_slice(&current_word);
-            current_word.clear();
-            // redo search from root
-            root.anagram_recursive(
-                seed_counter,
-                path,
-                root,
-                current_word,
-                previous_word,
-                results,
-            );
-            path.pop();
-            current_word.clear();
-            current_word.extend_from_slice(&previous_word);
-            previous_word.clear();
-            previous_word.extend_from_slice(&old_word);
+            self.path.pop();
         }
 
-        let mut inner_loop = |i: usize| {
-            let node = self.children[i].as_ref();
-            // skip node if it is None
-            if !node.is_some() {
+        for (i, child) in node.children.iter().enumerate() {
+            if self.truncated.is_some() {
                 return;
             }
-            let count = seed_counter[i];
-            if count == 0 {
+            let Some(child) = child else {
+                continue;
+            };
+            if self.target_counter[i] == 0 {
+                continue;
+            }
+            self.letters.push(i);
+            if compare_char_array(self.current_word(), self.previous_word()) {
+                self.letters.pop();
+                continue;
+            }
+
+            self.nodes += 1;
+            if let Some(reason) =
+                self.budget
+                    .exceeded(self.cancellation, &self.stopwatch, self.nodes)
+            {
+                self.truncated = Some(reason);
                 return;
             }
-            if compare_char_array(current_word, previous_word) {
+
+            self.target_counter[i] -= 1;
+            self.letters_left -= 1;
+            self.search(child);
+            self.letters_left += 1;
+            self.target_counter[i] += 1;
+            self.letters.pop();
+        }
+    }
+
+    /** Record every combination of words for the current path. */
+    fn expand(&mut self) {
+        let mut choices = vec![0; self.path.len()];
+        loop {
+            if self.budget.max_results == Some(self.found.len()) {
+                self.truncated = Some(Truncation::MaxResults);
                 return;
             }
-            seed_counter[i] -= 1; // decrement the count of the letter in the seed
-
-            path.push(i);
-            current_word.push(i);
-            // continue search from node
-            node.unwrap().anagram_recursive(
-                seed_counter,
-                path,
-                root,
-                current_word,
-                previous_word,
-                results,
+            self.found.push(
+                self.path
+                    .iter()
+                    .zip(&choices)
+                    .map(|(words, &choice)| words[choice])
+                    .collect(),
             );
-            path.pop(); // pop the letter from the path
-            current_word.pop(); // pop the letter from the current word
-            seed_counter[i] = count; // reset counter
-        };
 
-        for i in 0..ALPHA_SIZE {
-            inner_loop(i);
+            let Some(i) = (0..choices.len())
+                .rev()
+                .find(|&i| choices[i] + 1 < self.path[i].len())
+            else {
+                return;
+            };
+            choices[i] += 1;
+            // a word repeated on the path takes its ids in ascending order, as in `Anagrams`
+            for j in i + 1..choices.len() {
+                choices[j] = if ptr::eq(self.path[j], self.path[j - 1]) {
+                    choices[j - 1]
+                } else {
+                    0
+                };
+            }
         }
     }
 }
 
-pub fn trie_solve(
-    seed: &str,
-    min_length: usize,
-    excludes: &HashSet<String>,
-) -> (Vec<String>, Vec<String>) {
-    let dictionary = include_str!("dictionary.txt");
+/**
+ * The letter trie as an `Engine`, answering the same queries as `AnagramSolver` so the two can be
+ * checked against each other. Node budgets count trie nodes rather than candidate words.
+ */
+pub struct TrieEngine {
+    words: Vec<String>,
+    counts: Vec<u32>,
+}
+
+impl TrieEngine {
+    pub fn new(dictionary: &dyn Dictionary) -> Result<Self, AgarError> {
+        let (words, counts) = rank_words(dictionary).into_iter().unzip();
+        Ok(TrieEngine { words, counts })
+    }
+}
+
+impl Engine for TrieEngine {
+    fn name(&self) -> &'static str {
+        "trie"
+    }
+
+    fn solve(&self, query: &Query) -> Result<Solution, AgarError> {
+        // the same seed handling as `Anagrams::new`
+        let target = query
+            .seed
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_lowercase();
+        let mut target_counter = to_counter(&target)?;
+
+        let includes = query
+            .includes
+            .iter()
+            .map(|word| normalize_word(word))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        let excludes = query
+            .excludes
+            .iter()
+            .map(|word| normalize_word(word))
+            .collect::<HashSet<_>>();
+        for included in &includes {
+            let counter = to_counter(included)?;
+            if !counter_contains(&target_counter, &counter) {
+                return Ok(Solution::default());
+            }
+            subtract_counters(&mut target_counter, &counter);
+        }
+        let include_counts = includes
+            .iter()
+            .map(|word| {
+                self.words
+                    .iter()
+                    .position(|w| w == word)
+                    .map_or(0, |id| self.counts[id])
+            })
+            .collect::<Vec<_>>();
+
+        let mut trie = Trie::new();
+        let mut partials = Vec::new();
+        for (id, word) in self.words.iter().enumerate().take(query.top_n) {
+            if letter_count(word) >= query.min_length
+                && !excludes.contains(word)
+                && counter_contains(&target_counter, &to_counter(word)?)
+            {
+                trie.insert(word, id);
+                partials.push(word.clone());
+            }
+        }
 
-    let seed = str::replace(seed, " ", "").to_string();
+        let letters_left = target_counter.iter().map(|&count| count as usize).sum();
+        let mut search = TrieSearch {
+            root: &trie,
+            min_length: query.min_length,
+            max_num_words: query.max_num_words.saturating_sub(includes.len()),
+            target_counter,
+            letters_left,
+            letters: Vec::new(),
+            starts: vec![0],
+            path: Vec::new(),
+            found: Vec::new(),
+            budget: query.budget,
+            cancellation: &query.cancellation,
+            stopwatch: Stopwatch::start(),
+            nodes: 0,
+            truncated: None,
+        };
+        if letters_left > 0 && search.max_num_words > 0 {
+            search.search(&trie);
+        }
 
-    let lines: Vec<String> = dictionary.split("\n").map(str::to_string).collect();
+        let mut anagrams = search
+            .found
+            .iter()
+            .map(|ids| {
+                Anagram::new(
+                    &includes,
+                    &include_counts,
+                    ids.iter()
+                        .map(|&id| (self.words[id].as_str(), self.counts[id])),
+                    &*query.scorer,
+                )
+            })
+            .collect::<Vec<_>>();
+        glidesort::sort_by(&mut anagrams, |a, b| b.score.total_cmp(&a.score));
+
+        Ok(Solution {
+            anagrams,
+            partials,
+            truncated: search.truncated,
+        })
+    }
+}
 
-    let filter_line_closure = |line: &String| filter_line(line, &seed, min_length, excludes);
-    let processed_lines: Vec<String> = lines // using String as the return type of `to_lowercase`
-        .iter()
-        .map(process_line)
-        .filter(filter_line_closure)
-        .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{test_dictionary_with, test_solver};
 
-    let mut trie = Trie::new();
-    for line in &processed_lines {
-        trie.insert(line.clone());
+    /** Anagrams with their words sorted, in order, since engines may order words differently. */
+    fn sorted(anagrams: Vec<String>) -> Vec<String> {
+        let mut anagrams = anagrams
+            .iter()
+            .map(|anagram| {
+                let mut words = anagram.split(' ').collect::<Vec<_>>();
+                words.sort_unstable();
+                words.join(" ")
+            })
+            .collect::<Vec<_>>();
+        anagrams.sort_unstable();
+        anagrams
     }
 
-    let anagrams = trie.anagram(&seed);
+    #[test]
+    fn trie_solve_matches_the_solver() {
+        let dictionary = test_dictionary_with(|rank| 1000 - rank as u32);
+        let excludes = HashSet::from(["rat".to_string()]);
+        // non-letters in the seed are dropped, as the solver drops them
+        let (anagrams, partials) = trie_solve(&dictionary, "Stop-a rat", 3, &excludes).unwrap();
 
-    return (anagrams, processed_lines);
+        let mut query = Query::new("Stop-a rat");
+        query.max_num_words = 8;
+        query.excludes = excludes;
+        let solution = test_solver().solve(&query).unwrap();
+        let expected = solution.anagrams.iter().map(Anagram::to_string).collect();
+        let expected = sorted(expected);
+        assert!(!expected.is_empty());
+        assert_eq!(sorted(anagrams), expected);
+        assert_eq!(partials.len(), solution.partials.len());
+    }
+
+    #[test]
+    fn short_and_excluded_words_are_left_out() {
+        let dictionary = test_dictionary_with(|rank| 1000 - rank as u32);
+        let no_excludes = HashSet::new();
+
+        let (anagrams, partials) = trie_solve(&dictionary, "rat", 3, &no_excludes).unwrap();
+        assert_eq!(sorted(anagrams), ["art", "rat", "tar"]);
+        assert!(partials.iter().all(|word| word.len() >= 3));
+
+        let (anagrams, partials) = trie_solve(&dictionary, "oat", 1, &no_excludes).unwrap();
+        assert_eq!(sorted(anagrams), ["a to", "at o", "oat"]);
+        assert!(partials.contains(&"a".to_string()));
+
+        let excludes = HashSet::from(["Rat".to_string(), "art".to_string()]);
+        let (anagrams, partials) = trie_solve(&dictionary, "rat", 3, &excludes).unwrap();
+        assert_eq!(anagrams, ["tar"]);
+        assert_eq!(partials, ["tar"]);
+    }
 }
@@ -1,11 +1,27 @@
 import { Tab } from "@headlessui/react";
-import init, { js_generate } from "agar-man";
-import { Fragment, useEffect, useMemo, useState } from "react";
+import init, { AnagramSolver, Query } from "agar-man";
+import { Fragment, useEffect, useMemo, useRef, useState } from "react";
 import { GrReturn } from "react-icons/gr";
 import AutoSizer from "react-virtualized-auto-sizer";
 import { FixedSizeList as List } from "react-window";
 import { Poem } from "../components/Poem";
 
+// limits on a single search, so a long seed cannot hang the page
+const SEARCH_TIMEOUT_MS = 3000;
+const MAX_RESULTS = 200_000;
+
+/** A message for the reason a search was truncated, as the solver reports it. */
+const truncationMessage = (reason: string) => {
+  switch (reason) {
+    case "deadline":
+      return `the search took over ${SEARCH_TIMEOUT_MS / 1000}s, so only some results are shown`;
+    case "max_results":
+      return `only the first ${MAX_RESULTS.toLocaleString("en-US")} results were kept`;
+    default:
+      return `the search stopped early (${reason})`;
+  }
+};
+
 interface Rendered {
   seed: string;
   sentence: string;
@@ -286,6 +302,11 @@ export const Search = () => {
   });
 
   const [executionTime, setExecutionTime] = useState(0);
+  // why the last search stopped early, if it did
+  const [truncated, setTruncated] = useState<string>();
+
+  // preprocessing the dictionary is expensive, so keep one solver around
+  const solver = useRef<AnagramSolver>();
 
   useEffect(() => {
     generate({
@@ -315,21 +336,35 @@ export const Search = () => {
   }) => {
     setLoading(true);
     init().then(() => {
+      if (!solver.current) {
+        solver.current = new AnagramSolver();
+      }
       const start = window.performance.now();
-      const { anagrams, partials } = js_generate(
-        seed.toLowerCase(),
-        minLength,
-        maxWords,
-        excludes,
-        includes,
-        topN || 200_000
-      );
-      setResults([...anagrams]);
-      setPartials(
-        [...partials].sort((a, b) => {
-          return b.length - a.length;
-        })
-      );
+      const query = new Query(seed.toLowerCase());
+      query.min_length = minLength;
+      query.max_num_words = maxWords;
+      query.excludes = excludes;
+      query.includes = includes;
+      query.top_n = topN || 200_000;
+      // keep a huge seed from locking up the tab; the results say if the search was cut short
+      query.timeout_ms = SEARCH_TIMEOUT_MS;
+      query.max_results = MAX_RESULTS;
+      try {
+        const { anagrams, partials, truncated } = solver.current.generate(query);
+        setResults(anagrams.map((anagram) => anagram.words.join(" ")));
+        setTruncated(truncated);
+        setPartials(
+          [...partials].sort((a, b) => {
+            return b.length - a.length;
+          })
+        );
+      } catch (error) {
+        // invalid includes or an oversized seed
+        console.error(error);
+        setResults([]);
+        setPartials([]);
+        setTruncated(undefined);
+      }
       setRenderedSeed(seed);
       const end = window.performance.now();
       setExecutionTime(Math.floor(end - start));
@@ -357,6 +392,11 @@ export const Search = () => {
                   {results.length.toLocaleString("en-US")} results in{" "}
                   {(executionTime / 1000).toLocaleString("en-US")}s for "
                   {renderedSeed}"
+                  {truncated && (
+                    <span className="text-red-600">
+                      {truncationMessage(truncated)}
+                    </span>
+                  )}
                 </>
               )}
               <button